@@ -1,10 +1,29 @@
+use pallet_zksnark::artifacts::Circuit;
 use sc_service::ChainType;
-use solochain_template_runtime::{AccountId, Signature, WASM_BINARY};
+use solochain_template_runtime::{
+	AccountId, JoinSplitCircuitId, ShieldCircuitId, Signature, TransferCircuitId,
+	UnshieldCircuitId, WASM_BINARY,
+};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_consensus_grandpa::AuthorityId as GrandpaId;
 use sp_core::{sr25519, Pair, Public};
 use sp_runtime::traits::{IdentifyAccount, Verify};
 
+/// The keys the pallet's build sets up for its circuits, under the circuit
+/// IDs the runtime's calls verify against. They are finished with a public
+/// beacon rather than a ceremony, so they only suit development chains.
+fn verifying_keys() -> Vec<(u32, Vec<u8>)> {
+	[
+		(TransferCircuitId::get(), Circuit::Transfer),
+		(JoinSplitCircuitId::get(), Circuit::JoinSplit),
+		(ShieldCircuitId::get(), Circuit::Shield),
+		(UnshieldCircuitId::get(), Circuit::Unshield),
+	]
+	.into_iter()
+	.map(|(circuit_id, circuit)| (circuit_id, circuit.verification_key().to_vec()))
+	.collect()
+}
+
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
@@ -113,5 +132,8 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			"key": Some(root_key),
 		},
+		"zkSnark": {
+			"verifyingKeys": verifying_keys(),
+		},
 	})
 }
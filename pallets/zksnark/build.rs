@@ -1,5 +1,5 @@
     use std::process::Command;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use std::env;
 
     fn main() {
@@ -13,12 +13,91 @@
         compile_circuits(&build_dir);
     }
 
+    /// Circuits under `src/circuits` with a main component.
+    #[cfg(feature = "std")]
+    const CIRCUITS: &[&str] = &["enhanced_transaction", "join_split", "shield", "unshield"];
+
+    /// Powers of tau are made for circuits of up to `2^POWER` constraints.
+    #[cfg(feature = "std")]
+    const POWER: &str = "14";
+
+    /// Public randomness that finishes both setup phases, the SHA-256 of
+    /// `BEACON_NAME`. Anyone can rederive the toxic waste from it, so the
+    /// keys are only fit for development chains and tests.
+    #[cfg(feature = "std")]
+    const BEACON: &str = "07c4e2b1ee0f1841da9ddb16153dfacf0ed1ed9a73ad8908159690960bff56a3";
+
+    #[cfg(feature = "std")]
+    const BEACON_NAME: &str = "pallet-zksnark development keys";
+
+    /// The beacon is hashed `2^BEACON_ITERATIONS` times.
+    #[cfg(feature = "std")]
+    const BEACON_ITERATIONS: &str = "10";
+
     #[cfg(feature = "std")]
     fn compile_circuits(build_dir: &Path) {
+        let ptau = powers_of_tau(build_dir);
+        for circuit in CIRCUITS {
+            compile_circuit(build_dir, circuit);
+            set_up_keys(build_dir, circuit, &ptau);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn snarkjs(args: &[&str]) {
+        let status = Command::new("snarkjs")
+            .args(args)
+            .status()
+            .expect("Failed to run snarkjs");
+
+        assert!(status.success(), "snarkjs {} failed", args[..2].join(" "));
+    }
+
+    /// Phase 1 of the setup, shared by every circuit. Nothing in the source
+    /// tree goes into it, and it is by far the slowest step, so it is made
+    /// once per build directory.
+    #[cfg(feature = "std")]
+    fn powers_of_tau(build_dir: &Path) -> PathBuf {
+        let ptau = build_dir.join(format!("pot{}_final.ptau", POWER));
+        if ptau.exists() {
+            return ptau;
+        }
+
+        let path = |stage: &str| build_dir.join(format!("pot{}_{}.ptau", POWER, stage));
+        let (new, beacon, prepared) = (path("0000"), path("beacon"), path("prepared"));
+        let name = format!("-n={}", BEACON_NAME);
+        snarkjs(&["powersoftau", "new", "bn128", POWER, &new.to_string_lossy()]);
+        snarkjs(&[
+            "powersoftau",
+            "beacon",
+            &new.to_string_lossy(),
+            &beacon.to_string_lossy(),
+            BEACON,
+            BEACON_ITERATIONS,
+            &name,
+        ]);
+        snarkjs(&[
+            "powersoftau",
+            "prepare",
+            "phase2",
+            &beacon.to_string_lossy(),
+            &prepared.to_string_lossy(),
+        ]);
+
+        // Renamed only once complete, so an interrupted build starts over.
+        std::fs::rename(&prepared, &ptau).unwrap();
+        std::fs::remove_file(new).unwrap();
+        std::fs::remove_file(beacon).unwrap();
+        ptau
+    }
+
+    #[cfg(feature = "std")]
+    fn compile_circuit(build_dir: &Path, circuit: &str) {
         // Compile circuit
+        let source = format!("src/circuits/{}.circom", circuit);
         let status = Command::new("circom")
             .args(&[
-                "src/circuits/enhanced_transaction.circom",
+                source.as_str(),
                 "--r1cs",
                 "--wasm",
                 "--sym",
@@ -29,34 +108,28 @@
             .expect("Failed to compile circuit");
 
         assert!(status.success(), "Circuit compilation failed");
+    }
 
-        // Setup proving system
-        let pot_file_path = format!("{}/src/circuits/pot16_final.ptau", env::current_dir().unwrap().display());
-
-        let status = Command::new("snarkjs")
-            .args(&[
-                "groth16",
-                "setup",
-                &format!("{}/enhanced_transaction.r1cs", build_dir.display()),
-                &pot_file_path, // Correctly using the absolute path to pot16_final.ptau
-                &format!("{}/circuit_final.zkey", build_dir.display()),
-            ])
-            .status()
-            .expect("Failed to setup proving system");
-
-        assert!(status.success(), "Proving system setup failed");
-
-        // Export verification key
-        let status = Command::new("snarkjs")
-            .args(&[
-                "zkey",
-                "export",
-                "verificationkey",
-                &format!("{}/circuit_final.zkey", build_dir.display()),
-                &format!("{}/verification_key.json", build_dir.display()),
-            ])
-            .status()
-            .expect("Failed to export verification key");
+    /// Phase 2 for `circuit`, finished with the beacon, and its verification
+    /// key. Every step is deterministic, so the same circuit always gets the
+    /// same keys.
+    #[cfg(feature = "std")]
+    fn set_up_keys(build_dir: &Path, circuit: &str, ptau: &Path) {
+        let file = |suffix: &str| {
+            build_dir.join(format!("{}{}", circuit, suffix)).to_string_lossy().into_owned()
+        };
+        let (initial, zkey) = (file("_0000.zkey"), file("_final.zkey"));
 
-        assert!(status.success(), "Verification key export failed");
+        snarkjs(&["groth16", "setup", &file(".r1cs"), &ptau.to_string_lossy(), &initial]);
+        snarkjs(&[
+            "zkey",
+            "beacon",
+            &initial,
+            &zkey,
+            BEACON,
+            BEACON_ITERATIONS,
+            &format!("-n={}", BEACON_NAME),
+        ]);
+        snarkjs(&["zkey", "export", "verificationkey", &zkey, &file("_verification_key.json")]);
+        std::fs::remove_file(initial).unwrap();
     }
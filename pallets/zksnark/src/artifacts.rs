@@ -0,0 +1,80 @@
+//! The circom circuits under `src/circuits`, as `build.rs` compiles them,
+//! and the Groth16 keys it sets up for them.
+//!
+//! The keys are made without secret randomness: both setup phases are
+//! finished with a public beacon, so every build gets the same keys, and
+//! anyone can make proofs for them of false statements. They are for
+//! development chains and tests. A production chain registers keys from a
+//! trusted setup ceremony with `register_verifying_key`.
+//!
+//! Verification keys are small and compiled in. The other outputs are read
+//! from the build directory, so they are only found on the machine that
+//! built the crate.
+
+use std::path::PathBuf;
+
+/// Where `build.rs` writes its outputs.
+const BUILD_DIR: &str = concat!(env!("OUT_DIR"), "/zksnark");
+
+/// A circom circuit with a main component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Circuit {
+    /// `enhanced_transaction.circom`, for `private_transfer`.
+    Transfer,
+    /// `join_split.circom`, for `join_split`.
+    JoinSplit,
+    /// `shield.circom`, for `shield`.
+    Shield,
+    /// `unshield.circom`, for `unshield` and `pool_withdraw`.
+    Unshield,
+}
+
+/// The snarkjs verification key `build.rs` exported for `$name`.
+macro_rules! verification_key {
+    ($name:literal) => {
+        include_bytes!(concat!(env!("OUT_DIR"), "/zksnark/", $name, "_verification_key.json"))
+    };
+}
+
+impl Circuit {
+    /// Every circuit.
+    pub const ALL: [Circuit; 4] =
+        [Circuit::Transfer, Circuit::JoinSplit, Circuit::Shield, Circuit::Unshield];
+
+    /// Name of the circuit's source, and of every output made from it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Circuit::Transfer => "enhanced_transaction",
+            Circuit::JoinSplit => "join_split",
+            Circuit::Shield => "shield",
+            Circuit::Unshield => "unshield",
+        }
+    }
+
+    /// The snarkjs verification key, as `register_verifying_key` and the
+    /// genesis config take it.
+    pub const fn verification_key(self) -> &'static [u8] {
+        match self {
+            Circuit::Transfer => verification_key!("enhanced_transaction"),
+            Circuit::JoinSplit => verification_key!("join_split"),
+            Circuit::Shield => verification_key!("shield"),
+            Circuit::Unshield => verification_key!("unshield"),
+        }
+    }
+
+    /// The `.r1cs` circom compiled the circuit to.
+    pub fn r1cs_path(self) -> PathBuf {
+        PathBuf::from(BUILD_DIR).join(format!("{}.r1cs", self.name()))
+    }
+
+    /// The wasm circom compiled to compute the circuit's witnesses.
+    pub fn wasm_path(self) -> PathBuf {
+        let name = self.name();
+        PathBuf::from(BUILD_DIR).join(format!("{name}_js/{name}.wasm"))
+    }
+
+    /// The snarkjs proving key.
+    pub fn zkey_path(self) -> PathBuf {
+        PathBuf::from(BUILD_DIR).join(format!("{}_final.zkey", self.name()))
+    }
+}
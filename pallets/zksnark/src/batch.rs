@@ -0,0 +1,131 @@
+//! Batch verification of Groth16 proofs against a single key.
+//!
+//! Every valid proof `(A, B, C)` with public inputs folded into `L` satisfies
+//! `e(A, B) = e(alpha, beta) * e(L, gamma) * e(C, delta)`. Raising the i-th
+//! equation to a random `r_i` and multiplying them together gives
+//!
+//! ```text
+//! prod e(r_i * A_i, B_i) * e(-sum r_i * L_i, gamma) * e(-sum r_i * C_i, delta)
+//!     = e(alpha, beta)^(sum r_i)
+//! ```
+//!
+//! which costs one multi-Miller loop over `n + 2` pairs and a single final
+//! exponentiation instead of `n` full checks. A batch containing an invalid
+//! proof passes with probability at most `1 / 2^128` when the `r_i` are 128-bit
+//! values the submitter cannot predict.
+
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+use sp_std::vec::Vec;
+
+/// Check every proof in `proofs` at once, weighting the i-th proof by
+/// `randomizers[i]`.
+///
+/// Returns `false` if the combined check fails, if the slices differ in
+/// length, or if any proof has the wrong number of public inputs. It does
+/// not say which proof is bad; callers fall back to checking them one by one.
+pub fn verify<E: Pairing>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[(Proof<E>, Vec<E::ScalarField>)],
+    randomizers: &[E::ScalarField],
+) -> bool {
+    if proofs.len() != randomizers.len() {
+        return false;
+    }
+
+    let mut g1 = Vec::with_capacity(proofs.len() + 2);
+    let mut g2 = Vec::with_capacity(proofs.len() + 2);
+    let mut inputs_sum = E::G1::zero();
+    let mut c_sum = E::G1::zero();
+    let mut r_sum = E::ScalarField::zero();
+
+    for ((proof, inputs), r) in proofs.iter().zip(randomizers) {
+        let Ok(prepared_inputs) = Groth16::<E>::prepare_inputs(pvk, inputs) else {
+            return false;
+        };
+
+        g1.push(E::G1Prepared::from(proof.a * r));
+        g2.push(E::G2Prepared::from(proof.b));
+        inputs_sum += prepared_inputs * r;
+        c_sum += proof.c.into_group() * r;
+        r_sum += r;
+    }
+
+    // The prepared key holds -gamma and -delta, so the sums go in unnegated.
+    g1.push(E::G1Prepared::from(inputs_sum.into_affine()));
+    g2.push(pvk.gamma_g2_neg_pc.clone());
+    g1.push(E::G1Prepared::from(c_sum.into_affine()));
+    g2.push(pvk.delta_g2_neg_pc.clone());
+
+    match E::final_exponentiation(E::multi_miller_loop(g1, g2)) {
+        Some(result) => result.0 == pvk.alpha_g1_beta_g2.pow(r_sum.into_bigint()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::{read_proving_key, read_witness, CircomCircuit, CircomReduction, R1cs};
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::prepare_verifying_key;
+    use ark_snark::SNARK;
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        UniformRand,
+    };
+
+    /// A multiplier proof and the public inputs it proves.
+    type Statement = (Proof<Bn254>, Vec<Fr>);
+
+    fn statements(count: usize) -> (PreparedVerifyingKey<Bn254>, Vec<Statement>) {
+        let r1cs = R1cs::read(include_bytes!("circuits/test/multiplier.r1cs")).unwrap();
+        let witness = read_witness(include_bytes!("circuits/test/multiplier.wtns")).unwrap();
+        let pk = read_proving_key::<Bn254>(include_bytes!("circuits/test/multiplier_final.zkey"))
+            .unwrap();
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let statements = (0..count)
+            .map(|_| {
+                let circuit = CircomCircuit::new(&r1cs, witness.clone()).unwrap();
+                let inputs = circuit.public_signals().unwrap().to_vec();
+                let proof = Groth16::<Bn254, CircomReduction>::prove(&pk, circuit, &mut rng);
+                (proof.unwrap(), inputs)
+            })
+            .collect();
+        (prepare_verifying_key(&pk.vk), statements)
+    }
+
+    fn randomizers(count: usize) -> Vec<Fr> {
+        let mut rng = StdRng::seed_from_u64(1);
+        (0..count).map(|_| Fr::rand(&mut rng)).collect()
+    }
+
+    #[test]
+    fn accepts_a_batch_of_valid_proofs() {
+        let (pvk, statements) = statements(3);
+        assert!(verify(&pvk, &statements, &randomizers(3)));
+        assert!(verify(&pvk, &[], &[]));
+    }
+
+    #[test]
+    fn rejects_a_batch_with_one_bad_proof() {
+        let (pvk, mut statements) = statements(3);
+        statements[1].1[0] += Fr::from(1u64);
+        assert!(!verify(&pvk, &statements, &randomizers(3)));
+
+        let (_, mut statements) = self::statements(3);
+        statements[2].0.c = statements[0].0.a;
+        assert!(!verify(&pvk, &statements, &randomizers(3)));
+    }
+
+    #[test]
+    fn rejects_mismatched_randomizers_and_input_counts() {
+        let (pvk, mut statements) = statements(2);
+        assert!(!verify(&pvk, &statements, &randomizers(3)));
+
+        statements[0].1.pop();
+        assert!(!verify(&pvk, &statements, &randomizers(2)));
+    }
+}
@@ -0,0 +1,470 @@
+//! Benchmarking setup for pallet-zksnark.
+//!
+//! There is no prover in the runtime, so proofs are forged against keys made
+//! for the purpose. When a key has `gamma == delta`, the proof
+//! `(alpha, beta, -L)`, where `L` folds the public inputs into the key's `IC`
+//! points, satisfies the verification equation for any inputs, and checking
+//! it costs exactly what checking an honest proof does.
+//!
+//! Registered keys may be for either curve, so the benchmarks use BLS12-381,
+//! the slower of the two. Proofs use the compressed encoding, whose points
+//! are the most expensive to decode.
+//!
+//! `verify_with_json_key` and `verify_with_prepared_key` compare checking a
+//! proof against a snarkjs JSON key, parsed and prepared on every call, with
+//! checking it against the prepared form kept in the registry.
+//!
+//! Calls that move funds move the asset `Config::BenchmarkHelper` names, in
+//! multiples of its minimum balance, so that every payment can open the
+//! account it is made to. Where a fee is optional, it is paid.
+
+use super::*;
+#[allow(unused)]
+use crate::Pallet as ZkSnark;
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_serialize::CanonicalSerialize;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+
+/// Public inputs of the transfer circuit.
+const TRANSFER_INPUTS: u32 = 5;
+
+/// Public inputs of the join-split circuit.
+const JOIN_SPLIT_INPUTS: u32 = (join_split::INPUTS + join_split::OUTPUTS + 4) as u32;
+
+/// Public inputs of the shield circuit.
+const SHIELD_INPUTS: u32 = 3;
+
+/// Public inputs of the unshield circuit.
+const UNSHIELD_INPUTS: u32 = 7;
+
+/// The curve benchmarked keys are for.
+type BenchCurve = Bls12_381;
+
+type BenchScalar = <BenchCurve as Pairing>::ScalarField;
+
+/// A registry origin for the benchmarked runtime.
+fn registry_origin<T: Config>() -> Result<T::RuntimeOrigin, BenchmarkError> {
+    T::RegistryOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)
+}
+
+/// A pool origin for the benchmarked runtime.
+fn pool_origin<T: Config>() -> Result<T::RuntimeOrigin, BenchmarkError> {
+    T::PoolOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)
+}
+
+/// The benchmarked asset and the smallest amount of it that opens an
+/// account.
+fn asset_unit<T: Config>() -> (AssetIdOf<T>, BalanceOf<T>) {
+    let asset = T::BenchmarkHelper::asset();
+    let unit = T::Assets::minimum_balance(asset.clone()).max(1u32.into());
+    (asset, unit)
+}
+
+/// Mint `amount` of `asset` to `who`.
+fn fund<T: Config>(
+    asset: &AssetIdOf<T>,
+    who: &T::AccountId,
+    amount: BalanceOf<T>,
+) -> DispatchResult {
+    T::Assets::mint_into(asset.clone(), who, amount).map(|_| ())
+}
+
+/// `amount` as a note amount.
+fn note_amount<T: Config>(amount: BalanceOf<T>) -> Result<u64, BenchmarkError> {
+    amount.try_into().map_err(|_| BenchmarkError::Weightless)
+}
+
+/// A key for `public_inputs` inputs that [`forge`] can make proofs for.
+fn forgeable_key(public_inputs: u32) -> VerifyingKey<BenchCurve> {
+    let g1 = <BenchCurve as Pairing>::G1Affine::generator();
+    let g2 = <BenchCurve as Pairing>::G2Affine::generator();
+    let gamma = (g2 * BenchScalar::from(5u64)).into_affine();
+
+    VerifyingKey {
+        alpha_g1: (g1 * BenchScalar::from(2u64)).into_affine(),
+        beta_g2: (g2 * BenchScalar::from(3u64)).into_affine(),
+        gamma_g2: gamma,
+        delta_g2: gamma,
+        gamma_abc_g1: (0..=u64::from(public_inputs))
+            .map(|i| (g1 * BenchScalar::from(7 + i)).into_affine())
+            .collect(),
+    }
+}
+
+/// A snarkjs key of `len` bytes. It holds as many `IC` points as fit, so the
+/// work of registering it grows with its length, and is padded to `len` with
+/// trailing whitespace.
+fn key_of_length(len: u32) -> Vec<u8> {
+    let empty = BenchCurve::export_verification_key(&forgeable_key(0)).len();
+    let point = BenchCurve::export_verification_key(&forgeable_key(1)).len() - empty;
+    let inputs = (len as usize).saturating_sub(empty) / point;
+
+    let mut key = BenchCurve::export_verification_key(&forgeable_key(inputs as u32));
+    if key.len() < len as usize {
+        key.resize(len as usize, b' ');
+    }
+    key
+}
+
+/// Register `vk` for `circuit_id`.
+fn register<T: Config>(circuit_id: CircuitId, vk: &VerifyingKey<BenchCurve>) -> DispatchResult {
+    Pallet::<T>::do_register_verifying_key(circuit_id, BenchCurve::export_verification_key(vk))
+}
+
+fn compressed<P: CanonicalSerialize>(point: P) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(point.compressed_size());
+    point.serialize_compressed(&mut bytes).expect("writing to a Vec cannot fail; qed");
+    bytes
+}
+
+/// A proof of the big-endian `public_inputs` under `vk`, which must come
+/// from [`forgeable_key`].
+fn forge(circuit_id: CircuitId, vk: &VerifyingKey<BenchCurve>, public_inputs: &[u8]) -> Proof {
+    let inputs = PublicInputs::<BenchScalar>::from_be_bytes(public_inputs)
+        .expect("benchmark inputs are field elements; qed");
+    let folded = inputs
+        .as_slice()
+        .iter()
+        .zip(&vk.gamma_abc_g1[1..])
+        .fold(vk.gamma_abc_g1[0].into_group(), |sum, (input, point)| sum + *point * input);
+
+    Proof {
+        circuit_id,
+        a: compressed(vk.alpha_g1),
+        b: compressed(vk.beta_g2),
+        c: compressed((-folded).into_affine()),
+        encoding: ProofEncoding::Compressed,
+    }
+}
+
+/// Append a leaf to the commitment tree and return the root it leaves.
+fn known_root<T: Config>() -> Result<H256, DispatchError> {
+    Pallet::<T>::insert_leaf(H256::from_low_u64_be(u64::MAX))?;
+    Pallet::<T>::current_root()
+}
+
+/// The `index`-th transfer spent against `root`, proved under `vk`.
+fn transfer<T: Config>(
+    index: u32,
+    root: H256,
+    vk: &VerifyingKey<BenchCurve>,
+) -> Transfer<T::AccountId> {
+    let seed = 2 * u64::from(index);
+    // The proof is replaced once the inputs it binds are known.
+    let mut transfer = Transfer {
+        proof: forge(T::TransferCircuitId::get(), vk, &[]),
+        nullifier_hash: H256::from_low_u64_be(seed + 1),
+        commitment: H256::from_low_u64_be(seed + 2),
+        root,
+        recipient: account("recipient", index, 0),
+    };
+    let inputs = transfer.public_inputs(T::MaxTransferAmount::get());
+    transfer.proof = forge(T::TransferCircuitId::get(), vk, &inputs);
+    transfer
+}
+
+/// Create the pool of `denomination` notes of `asset`.
+fn open_pool<T: Config>(
+    asset: &AssetIdOf<T>,
+    denomination: BalanceOf<T>,
+) -> Result<PoolKeyOf<T>, BenchmarkError> {
+    Pallet::<T>::create_pool(pool_origin::<T>()?, asset.clone(), denomination)?;
+    Ok((asset.clone(), denomination))
+}
+
+/// A transfer proof, its public inputs and the key it was forged under.
+fn transfer_statement<T: Config>() -> (Proof, Vec<u8>, VerifyingKey<BenchCurve>) {
+    let vk = forgeable_key(TRANSFER_INPUTS);
+    let transfer = transfer::<T>(0, H256::zero(), &vk);
+    let inputs = transfer.public_inputs(T::MaxTransferAmount::get());
+    (transfer.proof, inputs, vk)
+}
+
+#[benchmarks]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn private_transfer() -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let vk = forgeable_key(TRANSFER_INPUTS);
+        register::<T>(T::TransferCircuitId::get(), &vk)?;
+        let Transfer { proof, nullifier_hash, commitment, root, recipient } =
+            transfer::<T>(0, known_root::<T>()?, &vk);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), proof, nullifier_hash, commitment, root, recipient);
+
+        assert!(NullifierSet::<T>::contains_key(nullifier_hash));
+        assert!(Commitments::<T>::contains_key(commitment));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn register_verifying_key(l: Linear<4_096, 131_072>) -> Result<(), BenchmarkError> {
+        let origin = registry_origin::<T>()?;
+        let key = key_of_length(l);
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, 0, key);
+
+        assert!(VerifyingKeys::<T>::contains_key(0));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn deprecate_verifying_key() -> Result<(), BenchmarkError> {
+        let origin = registry_origin::<T>()?;
+        register::<T>(0, &forgeable_key(TRANSFER_INPUTS))?;
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, 0);
+
+        assert_eq!(VerifyingKeys::<T>::get(0).map(|info| info.status), Some(KeyStatus::Deprecated));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn retire_verifying_key() -> Result<(), BenchmarkError> {
+        let origin = registry_origin::<T>()?;
+        register::<T>(0, &forgeable_key(TRANSFER_INPUTS))?;
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, 0);
+
+        assert_eq!(VerifyingKeys::<T>::get(0).map(|info| info.status), Some(KeyStatus::Retired));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn batch_private_transfer(
+        n: Linear<1, { T::MaxBatchSize::get() }>,
+    ) -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let vk = forgeable_key(TRANSFER_INPUTS);
+        register::<T>(T::TransferCircuitId::get(), &vk)?;
+        let root = known_root::<T>()?;
+        let transfers: Vec<_> = (0..n).map(|index| transfer::<T>(index, root, &vk)).collect();
+        let nullifiers: Vec<_> = transfers.iter().map(|t| t.nullifier_hash).collect();
+        let transfers = BoundedVec::try_from(transfers).map_err(|_| BenchmarkError::Weightless)?;
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), transfers);
+
+        assert!(nullifiers.iter().all(|n| NullifierSet::<T>::contains_key(n)));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn join_split() -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let vk = forgeable_key(JOIN_SPLIT_INPUTS);
+        register::<T>(T::JoinSplitCircuitId::get(), &vk)?;
+        let (asset, fee) = asset_unit::<T>();
+        fund::<T>(&asset, &Pallet::<T>::account_id(), fee)?;
+
+        let mut statement = JoinSplit {
+            proof: forge(T::JoinSplitCircuitId::get(), &vk, &[]),
+            root: known_root::<T>()?,
+            nullifier_hashes: [1, 2].map(H256::from_low_u64_be),
+            commitments: [3, 4].map(H256::from_low_u64_be),
+            relayer: account("relayer", 0, 0),
+            fee_asset: asset,
+            fee: note_amount::<T>(fee)?,
+        };
+        statement.proof = forge(T::JoinSplitCircuitId::get(), &vk, &statement.public_inputs());
+        let JoinSplit { proof, root, nullifier_hashes, commitments, relayer, fee_asset, fee } =
+            statement;
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller),
+            proof,
+            root,
+            nullifier_hashes,
+            commitments,
+            relayer,
+            fee_asset,
+            fee,
+        );
+
+        assert!(nullifier_hashes.iter().all(NullifierSet::<T>::contains_key));
+        assert!(commitments.iter().all(Commitments::<T>::contains_key));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn shield() -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let vk = forgeable_key(SHIELD_INPUTS);
+        register::<T>(T::ShieldCircuitId::get(), &vk)?;
+        let (asset, amount) = asset_unit::<T>();
+        fund::<T>(&asset, &caller, amount * 2u32.into())?;
+
+        let commitment = H256::from_low_u64_be(1);
+        let inputs = shield::shield_inputs(&commitment, &asset, note_amount::<T>(amount)?);
+        let proof = forge(T::ShieldCircuitId::get(), &vk, &inputs);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), asset, amount, commitment, proof);
+
+        assert!(Commitments::<T>::contains_key(commitment));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn unshield() -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let vk = forgeable_key(UNSHIELD_INPUTS);
+        register::<T>(T::UnshieldCircuitId::get(), &vk)?;
+        let (asset, fee) = asset_unit::<T>();
+        let amount = fee * 2u32.into();
+        fund::<T>(&asset, &Pallet::<T>::account_id(), amount)?;
+
+        let (nullifier_hash, root) = (H256::from_low_u64_be(1), known_root::<T>()?);
+        let recipient: T::AccountId = account("recipient", 0, 0);
+        let relayer: T::AccountId = account("relayer", 0, 0);
+        let inputs = shield::unshield_inputs(
+            &nullifier_hash,
+            &root,
+            &asset,
+            note_amount::<T>(amount)?,
+            &recipient,
+            &relayer,
+            note_amount::<T>(fee)?,
+        );
+        let proof = forge(T::UnshieldCircuitId::get(), &vk, &inputs);
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller),
+            proof,
+            nullifier_hash,
+            root,
+            asset,
+            amount,
+            recipient,
+            relayer,
+            fee,
+        );
+
+        assert!(NullifierSet::<T>::contains_key(nullifier_hash));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn create_pool() -> Result<(), BenchmarkError> {
+        let origin = pool_origin::<T>()?;
+        let (asset, denomination) = asset_unit::<T>();
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, asset.clone(), denomination);
+
+        assert!(Pools::<T>::contains_key((asset, denomination)));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn set_pool_status() -> Result<(), BenchmarkError> {
+        let origin = pool_origin::<T>()?;
+        let (asset, denomination) = asset_unit::<T>();
+        let key = open_pool::<T>(&asset, denomination)?;
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, asset, denomination, PoolStatus::Closed);
+
+        assert_eq!(Pools::<T>::get(key).map(|pool| pool.status), Some(PoolStatus::Closed));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn pool_deposit() -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let (asset, denomination) = asset_unit::<T>();
+        let key = open_pool::<T>(&asset, denomination)?;
+        fund::<T>(&asset, &caller, denomination * 2u32.into())?;
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), asset, denomination, H256::from_low_u64_be(1));
+
+        assert_eq!(Pools::<T>::get(key).map(|pool| pool.next_leaf_index), Some(1));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn pool_withdraw() -> Result<(), BenchmarkError> {
+        let caller: T::AccountId = whitelisted_caller();
+        let vk = forgeable_key(UNSHIELD_INPUTS);
+        register::<T>(T::UnshieldCircuitId::get(), &vk)?;
+        let (asset, fee) = asset_unit::<T>();
+        let denomination = fee * 2u32.into();
+        let key = open_pool::<T>(&asset, denomination)?;
+        let depositor: T::AccountId = account("depositor", 0, 0);
+        fund::<T>(&asset, &depositor, denomination * 2u32.into())?;
+        Pallet::<T>::pool_deposit(
+            RawOrigin::Signed(depositor).into(),
+            asset.clone(),
+            denomination,
+            H256::from_low_u64_be(1),
+        )?;
+
+        let pool = Pools::<T>::get(&key).ok_or(BenchmarkError::Weightless)?;
+        let root = *pool.roots.last().ok_or(BenchmarkError::Weightless)?;
+        let nullifier_hash = H256::from_low_u64_be(2);
+        let recipient: T::AccountId = account("recipient", 0, 0);
+        let relayer: T::AccountId = account("relayer", 0, 0);
+        let inputs = shield::unshield_inputs(
+            &nullifier_hash,
+            &root,
+            &asset,
+            note_amount::<T>(denomination)?,
+            &recipient,
+            &relayer,
+            note_amount::<T>(fee)?,
+        );
+        let proof = forge(T::UnshieldCircuitId::get(), &vk, &inputs);
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller),
+            asset,
+            denomination,
+            proof,
+            nullifier_hash,
+            root,
+            recipient,
+            relayer,
+            fee,
+        );
+
+        assert!(PoolNullifiers::<T>::contains_key(&key, nullifier_hash));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn verify_with_json_key() {
+        let (proof, inputs, vk) = transfer_statement::<T>();
+        let key = BenchCurve::export_verification_key(&vk);
+
+        #[block]
+        {
+            assert_eq!(Pallet::<T>::verify_groth16(&proof, &inputs, &key), Ok(true));
+        }
+    }
+
+    #[benchmark]
+    fn verify_with_prepared_key() -> Result<(), BenchmarkError> {
+        let (proof, inputs, vk) = transfer_statement::<T>();
+        register::<T>(proof.circuit_id, &vk)?;
+
+        #[block]
+        {
+            assert_eq!(Pallet::<T>::verify_proof(&proof, &inputs), Ok(true));
+        }
+
+        Ok(())
+    }
+
+    impl_benchmark_test_suite!(ZkSnark, crate::mock::new_test_ext(), crate::mock::Test);
+}
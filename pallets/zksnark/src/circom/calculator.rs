@@ -0,0 +1,382 @@
+//! Computing witnesses with the wasm circom writes with `--wasm`, without
+//! Node.
+//!
+//! This does what `witness_calculator.js` does. Every input signal is
+//! addressed by the FNV-1a hash of its name and written one field element
+//! at a time through the wasm's shared memory. Once all of them are set,
+//! the witness is read back the same way, one value per wire of the
+//! circuit's `.r1cs`.
+
+use ark_ff::{BigInteger, PrimeField};
+use core::marker::PhantomData;
+use serde_json::Value;
+use wasmi::{
+    core::HostError, errors::LinkerError, Caller, Engine, Error, Extern, Instance, Linker, Module,
+    Store, TypedFunc,
+};
+
+/// Reasons a witness could not be computed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalculatorError {
+    /// The wasm could not be instantiated or does not export circom's
+    /// runtime.
+    Wasm(String),
+    /// The wasm computes over another prime than the one it is run for.
+    FieldMismatch,
+    /// The inputs are not a JSON object, or a value is not an integer.
+    InvalidInput(String),
+    /// The circuit has no input signal of this name.
+    UnknownSignal(String),
+    /// An input signal was given the wrong number of values.
+    WrongSignalSize { signal: String, expected: usize, found: usize },
+    /// Fewer values were given than the circuit has inputs.
+    MissingInputs { set: usize, expected: usize },
+    /// The circuit rejected the inputs, for example with a failed assert.
+    Exception { code: i32, message: String },
+}
+
+impl From<Error> for CalculatorError {
+    fn from(error: Error) -> Self {
+        CalculatorError::Wasm(error.to_string())
+    }
+}
+
+/// What `exceptionHandler` traps with.
+#[derive(Debug)]
+struct Exception(i32);
+
+impl core::fmt::Display for Exception {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "circom exception {}", self.0)
+    }
+}
+
+impl HostError for Exception {}
+
+/// The error messages the circuit printed before raising an exception.
+#[derive(Default)]
+struct Runtime {
+    message: String,
+}
+
+/// The wasm's exports `witness_calculator.js` calls.
+struct Exports {
+    init: TypedFunc<i32, ()>,
+    read_shared_rw_memory: TypedFunc<i32, i32>,
+    write_shared_rw_memory: TypedFunc<(i32, i32), ()>,
+    get_input_signal_size: TypedFunc<(i32, i32), i32>,
+    set_input_signal: TypedFunc<(i32, i32, i32), ()>,
+    get_input_size: TypedFunc<(), i32>,
+    get_witness: TypedFunc<i32, ()>,
+}
+
+/// A circuit's witness calculator, for witnesses over `F`.
+pub struct WitnessCalculator<F> {
+    store: Store<Runtime>,
+    exports: Exports,
+    /// Number of 32-bit words in a field element.
+    n32: usize,
+    witness_size: usize,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField> WitnessCalculator<F> {
+    /// Instantiate the wasm of a circuit, such as the ones `build.rs`
+    /// compiles to [`crate::artifacts::Circuit::wasm_path`]. A wasm for
+    /// another prime than the modulus of `F` is refused.
+    pub fn new(wasm: &[u8]) -> Result<Self, CalculatorError> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, wasm)?;
+        let mut store = Store::new(&engine, Runtime::default());
+
+        let linker = runtime(&engine).map_err(|error| CalculatorError::Wasm(error.to_string()))?;
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+
+        let exports = Exports {
+            init: func(&store, &instance, "init")?,
+            read_shared_rw_memory: func(&store, &instance, "readSharedRWMemory")?,
+            write_shared_rw_memory: func(&store, &instance, "writeSharedRWMemory")?,
+            get_input_signal_size: func(&store, &instance, "getInputSignalSize")?,
+            set_input_signal: func(&store, &instance, "setInputSignal")?,
+            get_input_size: func(&store, &instance, "getInputSize")?,
+            get_witness: func(&store, &instance, "getWitness")?,
+        };
+        let n32 = func::<(), i32>(&store, &instance, "getFieldNumLen32")?.call(&mut store, ())?;
+        let witness_size =
+            func::<(), i32>(&store, &instance, "getWitnessSize")?.call(&mut store, ())?;
+
+        let mut calculator = WitnessCalculator {
+            store,
+            exports,
+            n32: n32 as usize,
+            witness_size: witness_size as usize,
+            _field: PhantomData,
+        };
+        func::<(), ()>(&calculator.store, &instance, "getRawPrime")?
+            .call(&mut calculator.store, ())?;
+        let mut modulus = F::MODULUS.to_bytes_le();
+        modulus.resize(4 * calculator.n32, 0);
+        if calculator.read_shared_memory()? != modulus {
+            return Err(CalculatorError::FieldMismatch);
+        }
+        Ok(calculator)
+    }
+
+    /// Number of values in a witness, which is the number of wires.
+    pub fn witness_size(&self) -> usize {
+        self.witness_size
+    }
+
+    /// Compute the witness for `inputs`, a JSON object from signal names to
+    /// values such as the `input.json` given to `generate_witness.js`.
+    ///
+    /// Values are integers or decimal strings and are reduced modulo the
+    /// prime. An array signal such as `merklePathElements` takes a (nested)
+    /// array of values, and a bus input an object of its fields.
+    pub fn calculate(&mut self, inputs: &Value) -> Result<Vec<F>, CalculatorError> {
+        if !inputs.is_object() {
+            return Err(CalculatorError::InvalidInput(String::from("inputs are not an object")));
+        }
+        let mut signals = Vec::new();
+        qualify(String::new(), inputs, &mut signals);
+
+        self.store.data_mut().message.clear();
+        self.call(self.exports.init, 0)?;
+        let mut set = 0;
+        for (signal, values) in signals {
+            let hash = fnv1a(&signal);
+            let (msb, lsb) = ((hash >> 32) as i32, hash as i32);
+            let size = self.call(self.exports.get_input_signal_size, (msb, lsb))?;
+            // No signal is empty, and the wasm reports names it does not
+            // know as having no values.
+            let expected = match usize::try_from(size) {
+                Ok(size) if size > 0 => size,
+                _ => return Err(CalculatorError::UnknownSignal(signal)),
+            };
+            if values.len() != expected {
+                return Err(CalculatorError::WrongSignalSize {
+                    signal,
+                    expected,
+                    found: values.len(),
+                });
+            }
+            for (index, value) in values.into_iter().enumerate() {
+                let element = element::<F>(value).ok_or_else(|| {
+                    CalculatorError::InvalidInput(format!("{signal}[{index}] is not an integer"))
+                })?;
+                self.write_shared_memory(element)?;
+                self.call(self.exports.set_input_signal, (msb, lsb, index as i32))?;
+                set += 1;
+            }
+        }
+        let expected = self.call(self.exports.get_input_size, ())? as usize;
+        if set < expected {
+            return Err(CalculatorError::MissingInputs { set, expected });
+        }
+
+        (0..self.witness_size)
+            .map(|index| {
+                self.call(self.exports.get_witness, index as i32)?;
+                let bytes = self.read_shared_memory()?;
+                Ok(F::from_le_bytes_mod_order(&bytes))
+            })
+            .collect()
+    }
+
+    /// Call an export, turning a trap from `exceptionHandler` into the
+    /// exception the circuit raised.
+    fn call<P: wasmi::WasmParams, R: wasmi::WasmResults>(
+        &mut self,
+        func: TypedFunc<P, R>,
+        params: P,
+    ) -> Result<R, CalculatorError> {
+        func.call(&mut self.store, params).map_err(|error| match error.downcast_ref() {
+            Some(Exception(code)) => CalculatorError::Exception {
+                code: *code,
+                message: self.store.data().message.clone(),
+            },
+            None => error.into(),
+        })
+    }
+
+    fn read_shared_memory(&mut self) -> Result<Vec<u8>, CalculatorError> {
+        let mut bytes = Vec::with_capacity(4 * self.n32);
+        for word in 0..self.n32 {
+            let value = self.call(self.exports.read_shared_rw_memory, word as i32)?;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    fn write_shared_memory(&mut self, element: F) -> Result<(), CalculatorError> {
+        let mut bytes = element.into_bigint().to_bytes_le();
+        bytes.resize(4 * self.n32, 0);
+        for (word, value) in bytes.chunks_exact(4).enumerate() {
+            let value = i32::from_le_bytes(value.try_into().expect("chunks of 4 bytes; qed"));
+            self.call(self.exports.write_shared_rw_memory, (word as i32, value))?;
+        }
+        Ok(())
+    }
+}
+
+/// The host functions `witness_calculator.js` gives the wasm.
+fn runtime(engine: &Engine) -> Result<Linker<Runtime>, LinkerError> {
+    let mut linker = Linker::new(engine);
+    linker
+        .func_wrap("runtime", "exceptionHandler", |code: i32| -> Result<(), Error> {
+            Err(Error::host(Exception(code)))
+        })?
+        .func_wrap("runtime", "printErrorMessage", |mut caller: Caller<Runtime>| {
+            let message = read_message(&mut caller)?;
+            caller.data_mut().message.push_str(&message);
+            caller.data_mut().message.push('\n');
+            Ok(())
+        })?
+        // Output of circom's `log`, which is dropped.
+        .func_wrap("runtime", "writeBufferMessage", |mut caller: Caller<Runtime>| {
+            read_message(&mut caller).map(drop)
+        })?
+        .func_wrap("runtime", "showSharedRWMemory", || {})?;
+    Ok(linker)
+}
+
+fn func<P: wasmi::WasmParams, R: wasmi::WasmResults>(
+    store: &Store<Runtime>,
+    instance: &Instance,
+    name: &str,
+) -> Result<TypedFunc<P, R>, CalculatorError> {
+    Ok(instance.get_typed_func(store, name)?)
+}
+
+/// Drain the message the circuit is passing to the host, one character per
+/// call of `getMessageChar` until a zero.
+fn read_message(caller: &mut Caller<Runtime>) -> Result<String, Error> {
+    let next = caller
+        .get_export("getMessageChar")
+        .and_then(Extern::into_func)
+        .ok_or_else(|| Error::new("getMessageChar is not exported"))?
+        .typed::<(), i32>(&*caller)?;
+    let mut message = String::new();
+    loop {
+        match next.call(&mut *caller, ())? {
+            0 => return Ok(message),
+            c => message.push(char::from(c as u8)),
+        }
+    }
+}
+
+/// Flatten `value` into `(signal, values)` pairs the way
+/// `witness_calculator.js` qualifies its input names.
+fn qualify<'a>(prefix: String, value: &'a Value, signals: &mut Vec<(String, Vec<&'a Value>)>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                let name =
+                    if prefix.is_empty() { name.clone() } else { format!("{prefix}.{name}") };
+                qualify(name, field, signals);
+            }
+        }
+        Value::Array(items) => {
+            let mut values = Vec::new();
+            flatten(value, &mut values);
+            if values.first().is_some_and(|value| value.is_object()) {
+                // An array of buses, whose fields are signals.
+                for (index, item) in items.iter().enumerate() {
+                    qualify(format!("{prefix}[{index}]"), item, signals);
+                }
+            } else {
+                signals.push((prefix, values));
+            }
+        }
+        _ => signals.push((prefix, vec![value])),
+    }
+}
+
+fn flatten<'a>(value: &'a Value, values: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| flatten(item, values)),
+        _ => values.push(value),
+    }
+}
+
+fn element<F: PrimeField>(value: &Value) -> Option<F> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+/// The 64-bit FNV-1a hash circom addresses signals by.
+fn fnv1a(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use serde_json::json;
+
+    const CHAIN: &[u8] = include_bytes!("../circuits/test/multiplier_chain.wasm");
+
+    fn calculator() -> WitnessCalculator<Fr> {
+        WitnessCalculator::new(CHAIN).unwrap()
+    }
+
+    #[test]
+    fn computes_the_witness_generate_witness_js_does() {
+        let mut calculator = calculator();
+        assert_eq!(calculator.witness_size(), 1004);
+
+        let (a, b, c) = (Fr::from(1u64), Fr::from(2u64), Fr::from(3u64));
+        let mut chain = vec![a * a + b * Fr::from(2u64) + c + Fr::from(3u64)];
+        for _ in 1..1000 {
+            let last = chain[chain.len() - 1];
+            chain.push(last * last + b + Fr::from(4u64));
+        }
+        // `int[999]` is `d`, which circom keeps a single wire for.
+        let mut expected = vec![Fr::from(1u64), chain[999], a, b, c];
+        expected.extend(&chain[..999]);
+
+        let witness = calculator.calculate(&json!({ "a": 1, "b": "2", "c": "3" }));
+        assert_eq!(witness, Ok(expected));
+        // Values are reduced like snarkjs reduces them, negative ones included.
+        let witness = calculator.calculate(&json!({ "a": "-1", "b": 2, "c": 3 })).unwrap();
+        assert_eq!(witness[2], -a);
+    }
+
+    #[test]
+    fn rejects_inputs_the_circuit_does_not_take() {
+        let mut calculator = calculator();
+        assert_eq!(
+            calculator.calculate(&json!({ "a": 1, "b": 2 })),
+            Err(CalculatorError::MissingInputs { set: 2, expected: 3 })
+        );
+        assert_eq!(
+            calculator.calculate(&json!({ "a": 1, "b": 2, "c": 3, "e": 4 })),
+            Err(CalculatorError::UnknownSignal("e".into()))
+        );
+        assert_eq!(
+            calculator.calculate(&json!({ "a": [1, 2], "b": 2, "c": 3 })),
+            Err(CalculatorError::WrongSignalSize { signal: "a".into(), expected: 1, found: 2 })
+        );
+        assert!(matches!(
+            calculator.calculate(&json!({ "a": "one", "b": 2, "c": 3 })),
+            Err(CalculatorError::InvalidInput(_))
+        ));
+        assert_eq!(
+            WitnessCalculator::<ark_bls12_381::Fr>::new(CHAIN).map(|_| ()),
+            Err(CalculatorError::FieldMismatch)
+        );
+    }
+
+    #[test]
+    fn hashes_names_like_circom() {
+        // `fnvHash("a")` in `witness_calculator.js`.
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+    }
+}
@@ -0,0 +1,220 @@
+//! Proving circom circuits with arkworks.
+//!
+//! Circuits stay authored in circom and compiled by `build.rs`. Their
+//! `.r1cs` is read with [`R1cs::read`] and the snarkjs proving key with
+//! [`read_proving_key`]. A witness is computed from the circuit's wasm by
+//! [`WitnessCalculator`], or read with [`read_witness`] when it comes from
+//! `generate_witness.js`. [`CircomCircuit`] turns the constraints and
+//! the witness into a [`ConstraintSynthesizer`], and [`prove`] makes a
+//! proof with it that the key's verification key accepts, exactly as
+//! `snarkjs groth16 prove` would.
+
+pub mod calculator;
+pub mod r1cs;
+pub mod reader;
+pub mod reduction;
+pub mod wtns;
+pub mod zkey;
+
+pub use calculator::{CalculatorError, WitnessCalculator};
+pub use r1cs::R1cs;
+pub use reduction::CircomReduction;
+pub use wtns::read_witness;
+pub use zkey::read_proving_key;
+
+use crate::{
+    curve::PointError,
+    prover::{self, ProvenStatement, ProverError},
+    registry::CircuitId,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_groth16::ProvingKey;
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable},
+};
+use ark_std::rand::{CryptoRng, RngCore};
+
+/// Reasons a circom or snarkjs file, or a witness, can be rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircomError {
+    /// The file does not start with the magic of the expected file type.
+    WrongFileType,
+    /// The file is of a version this module does not read.
+    UnsupportedVersion,
+    /// The file ends before a value it declares.
+    Truncated,
+    /// A section the file type requires is missing.
+    MissingSection(u32),
+    /// The file is over another prime than the one it is read for.
+    FieldMismatch,
+    /// A field element is not below the modulus.
+    NonCanonicalElement,
+    /// A point is off the curve or outside the prime-order subgroup.
+    InvalidPoint(PointError),
+    /// The `.zkey` is not for Groth16.
+    UnsupportedProtocol,
+    /// A wire index or count is outside the circuit.
+    WireOutOfRange,
+    /// The witness does not have one value per wire, starting with one.
+    WitnessMismatch,
+}
+
+/// A circom circuit, with or without a witness.
+#[derive(Clone, Debug)]
+pub struct CircomCircuit<'a, F> {
+    r1cs: &'a R1cs<F>,
+    witness: Option<Vec<F>>,
+}
+
+impl<'a, F: PrimeField> CircomCircuit<'a, F> {
+    /// The shape of `r1cs`, for setting up keys.
+    pub fn setup(r1cs: &'a R1cs<F>) -> Self {
+        CircomCircuit { r1cs, witness: None }
+    }
+
+    /// `r1cs` assigned `witness`, ready to be proven.
+    pub fn new(r1cs: &'a R1cs<F>, witness: Vec<F>) -> Result<Self, CircomError> {
+        if witness.len() != r1cs.wires || witness.first() != Some(&F::one()) {
+            return Err(CircomError::WitnessMismatch);
+        }
+        Ok(CircomCircuit { r1cs, witness: Some(witness) })
+    }
+
+    /// The public signals of the witness, outputs first, as snarkjs
+    /// writes them to `public.json`.
+    pub fn public_signals(&self) -> Option<&[F]> {
+        let witness = self.witness.as_ref()?;
+        Some(&witness[1..=self.r1cs.public_count()])
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<'_, F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let value = |wire: usize| {
+            let witness = self.witness.as_ref();
+            move || {
+                witness
+                    .and_then(|witness| witness.get(wire).copied())
+                    .ok_or(SynthesisError::AssignmentMissing)
+            }
+        };
+
+        // Allocated in wire order, the instance and witness assignments
+        // concatenate to circom's witness.
+        let mut variables = Vec::with_capacity(self.r1cs.wires);
+        variables.push(Variable::One);
+        for wire in 1..self.r1cs.wires {
+            let variable = if wire <= self.r1cs.public_count() {
+                cs.new_input_variable(value(wire))?
+            } else {
+                cs.new_witness_variable(value(wire))?
+            };
+            variables.push(variable);
+        }
+
+        let combination = |terms: &[(usize, F)]| {
+            terms
+                .iter()
+                .fold(lc!(), |lc: LinearCombination<F>, (wire, coefficient)| {
+                    lc + (*coefficient, variables[*wire])
+                })
+        };
+        for constraint in &self.r1cs.constraints {
+            cs.enforce_constraint(
+                combination(&constraint.a),
+                combination(&constraint.b),
+                combination(&constraint.c),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Prove `circuit` under `circuit_id` with a proving key read from a
+/// `.zkey`.
+///
+/// The key must have been set up for `circuit`'s `.r1cs`; a key for
+/// another circuit is refused rather than producing a proof that does not
+/// verify.
+pub fn prove<E, R>(
+    pk: &ProvingKey<E>,
+    circuit: CircomCircuit<E::ScalarField>,
+    circuit_id: CircuitId,
+    rng: &mut R,
+) -> Result<ProvenStatement, ProverError>
+where
+    E: Pairing,
+    R: RngCore + CryptoRng,
+{
+    let r1cs = circuit.r1cs;
+    if pk.a_query.len() != r1cs.wires || pk.vk.gamma_abc_g1.len() != r1cs.public_count() + 1 {
+        return Err(ProverError::KeyMismatch);
+    }
+    prover::prove_with_reduction::<E, CircomReduction, _, _>(pk, circuit, circuit_id, rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_snark::SNARK;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    const R1CS: &[u8] = include_bytes!("../circuits/test/multiplier.r1cs");
+    const WITNESS: &[u8] = include_bytes!("../circuits/test/multiplier.wtns");
+    const ZKEY: &[u8] = include_bytes!("../circuits/test/multiplier_final.zkey");
+
+    fn multiplier() -> (R1cs<Fr>, Vec<Fr>) {
+        (R1cs::read(R1CS).unwrap(), read_witness(WITNESS).unwrap())
+    }
+
+    #[test]
+    fn proves_against_the_snarkjs_key() {
+        let (r1cs, witness) = multiplier();
+        let pk = read_proving_key::<Bn254>(ZKEY).unwrap();
+        let circuit = CircomCircuit::new(&r1cs, witness).unwrap();
+        assert_eq!(circuit.public_signals(), Some(&[Fr::from(102u64), Fr::from(11u64)][..]));
+
+        let statement = prove(&pk, circuit, 5, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(prover::verify(&pk.vk, &statement.proof, &statement.public_inputs), Ok(true));
+    }
+
+    #[test]
+    fn keys_set_up_natively_use_the_same_reduction() {
+        let (r1cs, witness) = multiplier();
+        let mut rng = StdRng::seed_from_u64(0);
+        let (pk, vk) = Groth16::<Bn254, CircomReduction>::circuit_specific_setup(
+            CircomCircuit::setup(&r1cs),
+            &mut rng,
+        )
+        .unwrap();
+
+        let circuit = CircomCircuit::new(&r1cs, witness).unwrap();
+        let statement = prove(&pk, circuit, 5, &mut rng).unwrap();
+        assert_eq!(prover::verify(&vk, &statement.proof, &statement.public_inputs), Ok(true));
+    }
+
+    #[test]
+    fn rejects_witnesses_and_keys_of_other_circuits() {
+        let (r1cs, mut witness) = multiplier();
+        let pk = read_proving_key::<Bn254>(ZKEY).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert_eq!(
+            CircomCircuit::new(&r1cs, witness[1..].to_vec()).map(|_| ()),
+            Err(CircomError::WitnessMismatch)
+        );
+
+        witness[1] = Fr::from(103u64);
+        let circuit = CircomCircuit::new(&r1cs, witness.clone()).unwrap();
+        assert_eq!(prove(&pk, circuit, 5, &mut rng), Err(ProverError::Unsatisfied));
+
+        let mut other = pk.clone();
+        other.vk.gamma_abc_g1.pop();
+        let circuit = CircomCircuit::new(&r1cs, witness).unwrap();
+        assert_eq!(prove(&other, circuit, 5, &mut rng), Err(ProverError::KeyMismatch));
+    }
+}
@@ -0,0 +1,112 @@
+//! Reading the `.r1cs` files circom writes with `--r1cs`.
+//!
+//! Wires are numbered the way the witness is laid out: the constant one,
+//! the public outputs, the public inputs, then everything private. Each
+//! constraint is `a * b = c` over sparse linear combinations of wires.
+
+use super::{
+    reader::{Reader, Sections},
+    CircomError,
+};
+use ark_ff::PrimeField;
+
+const MAGIC: &[u8; 4] = b"r1cs";
+const VERSION: u32 = 1;
+
+const HEADER: u32 = 1;
+const CONSTRAINTS: u32 = 2;
+
+/// A linear combination, as `(wire, coefficient)` pairs.
+pub type Terms<F> = Vec<(usize, F)>;
+
+/// One `a * b = c` constraint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint<F> {
+    pub a: Terms<F>,
+    pub b: Terms<F>,
+    pub c: Terms<F>,
+}
+
+/// A circuit's constraints and the layout of its wires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct R1cs<F> {
+    /// Number of wires, including the constant one.
+    pub wires: usize,
+    pub public_outputs: usize,
+    pub public_inputs: usize,
+    pub private_inputs: usize,
+    pub constraints: Vec<Constraint<F>>,
+}
+
+impl<F: PrimeField> R1cs<F> {
+    /// Parse an `.r1cs` file over `F`. Files for another prime are refused.
+    pub fn read(bytes: &[u8]) -> Result<Self, CircomError> {
+        let sections = Sections::read(bytes, MAGIC, VERSION)?;
+
+        let mut header = sections.get(HEADER)?;
+        let n8 = header.prime::<F>()?;
+        let wires = header.usize()?;
+        let public_outputs = header.usize()?;
+        let public_inputs = header.usize()?;
+        let private_inputs = header.usize()?;
+        let _labels = header.u64()?;
+        let count = header.usize()?;
+        if 1 + public_outputs + public_inputs + private_inputs > wires {
+            return Err(CircomError::WireOutOfRange);
+        }
+
+        let mut body = sections.get(CONSTRAINTS)?;
+        let mut terms = || read_terms(&mut body, n8, wires);
+        let constraints = (0..count)
+            .map(|_| Ok(Constraint { a: terms()?, b: terms()?, c: terms()? }))
+            .collect::<Result<_, CircomError>>()?;
+
+        Ok(R1cs { wires, public_outputs, public_inputs, private_inputs, constraints })
+    }
+
+    /// Number of public signals: the outputs, then the public inputs.
+    pub fn public_count(&self) -> usize {
+        self.public_outputs + self.public_inputs
+    }
+}
+
+fn read_terms<F: PrimeField>(
+    reader: &mut Reader,
+    n8: usize,
+    wires: usize,
+) -> Result<Terms<F>, CircomError> {
+    let count = reader.usize()?;
+    (0..count)
+        .map(|_| {
+            let wire = reader.usize()?;
+            if wire >= wires {
+                return Err(CircomError::WireOutOfRange);
+            }
+            Ok((wire, reader.element(n8)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPLIER: &[u8] = include_bytes!("../circuits/test/multiplier.r1cs");
+
+    #[test]
+    fn reads_the_multiplier() {
+        let r1cs = R1cs::<ark_bn254::Fr>::read(MULTIPLIER).unwrap();
+        assert_eq!(
+            (r1cs.wires, r1cs.public_outputs, r1cs.public_inputs, r1cs.private_inputs),
+            (5, 1, 1, 1)
+        );
+        assert_eq!(r1cs.public_count(), 2);
+        assert_eq!(r1cs.constraints.len(), 2);
+
+        assert_eq!(R1cs::<ark_bls12_381::Fr>::read(MULTIPLIER), Err(CircomError::FieldMismatch));
+        assert_eq!(
+            R1cs::<ark_bn254::Fr>::read(&MULTIPLIER[..MULTIPLIER.len() - 1]),
+            Err(CircomError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! The section container shared by circom's and snarkjs' binary files.
+//!
+//! A file starts with a four byte magic, a `u32` version and a `u32`
+//! section count. Each section is a `u32` type and a `u64` length followed
+//! by its body. Every integer is little-endian.
+
+use super::CircomError;
+use crate::curve::decode_canonical;
+use ark_ff::{BigInteger, PrimeField};
+
+/// The sections of a file, in the order they were written.
+pub struct Sections<'a> {
+    sections: Vec<(u32, &'a [u8])>,
+}
+
+impl<'a> Sections<'a> {
+    /// Split `bytes` into sections, checking the magic and version.
+    pub fn read(bytes: &'a [u8], magic: &[u8; 4], version: u32) -> Result<Self, CircomError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(4)? != magic {
+            return Err(CircomError::WrongFileType);
+        }
+        if reader.u32()? != version {
+            return Err(CircomError::UnsupportedVersion);
+        }
+        let count = reader.u32()?;
+        let mut sections = Vec::new();
+        for _ in 0..count {
+            let section = reader.u32()?;
+            let len = usize::try_from(reader.u64()?).map_err(|_| CircomError::Truncated)?;
+            sections.push((section, reader.take(len)?));
+        }
+        Ok(Sections { sections })
+    }
+
+    /// A reader over the body of the first section of type `section`.
+    pub fn get(&self, section: u32) -> Result<Reader<'a>, CircomError> {
+        self.sections
+            .iter()
+            .find(|(id, _)| *id == section)
+            .map(|(_, body)| Reader::new(body))
+            .ok_or(CircomError::MissingSection(section))
+    }
+}
+
+/// A cursor over little-endian values.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], CircomError> {
+        if self.bytes.len() < len {
+            return Err(CircomError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, CircomError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("took 4 bytes; qed")))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, CircomError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("took 8 bytes; qed")))
+    }
+
+    /// A `u32` count or index.
+    pub fn usize(&mut self) -> Result<usize, CircomError> {
+        usize::try_from(self.u32()?).map_err(|_| CircomError::Truncated)
+    }
+
+    /// A field element of `n8` little-endian bytes in its standard form.
+    pub fn element<F: PrimeField>(&mut self, n8: usize) -> Result<F, CircomError> {
+        let mut bytes = self.take(n8)?.to_vec();
+        bytes.reverse();
+        decode_canonical(&bytes).ok_or(CircomError::NonCanonicalElement)
+    }
+
+    /// Read the `n8` and prime a header starts with, checking the prime is
+    /// the modulus of `F`. Returns `n8`.
+    pub fn prime<F: PrimeField>(&mut self) -> Result<usize, CircomError> {
+        let n8 = self.usize()?;
+        let prime = self.take(n8)?;
+        let mut modulus = F::MODULUS.to_bytes_le();
+        modulus.resize(n8.max(modulus.len()), 0);
+        if prime != modulus.as_slice() {
+            return Err(CircomError::FieldMismatch);
+        }
+        Ok(n8)
+    }
+}
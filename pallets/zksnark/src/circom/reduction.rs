@@ -0,0 +1,86 @@
+//! The R1CS-to-QAP reduction snarkjs proves with.
+//!
+//! arkworks evaluates the quotient `h = (a * b - c) / z` on a coset of the
+//! constraint domain and commits to its coefficients. snarkjs instead
+//! evaluates `a * b - c` on the odd powers of the root of unity of twice
+//! the domain's size, where `z` is the constant `-2`, and folds `z` into
+//! the `H` points of the `.zkey`. Proving against a `.zkey` therefore
+//! needs the same evaluation points.
+
+use ark_ff::PrimeField;
+use ark_groth16::r1cs_to_qap::{evaluate_constraint, LibsnarkReduction, R1CSToQAP};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSystemRef, Result as R1CSResult, SynthesisError,
+};
+
+/// The reduction of snarkjs' `groth16 prove`, for use as the `QAP` of
+/// [`ark_groth16::Groth16`].
+pub struct CircomReduction;
+
+impl R1CSToQAP for CircomReduction {
+    #[allow(clippy::type_complexity)]
+    fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> R1CSResult<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize)> {
+        // The polynomials are the same, only the quotient's basis differs.
+        LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)
+    }
+
+    fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        let domain =
+            D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let domain_size = domain.size();
+        let coset = D::new(2 * domain_size)
+            .and_then(|double| domain.get_coset(double.group_gen()))
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        let evaluate = |rows: &[Vec<(F, usize)>]| {
+            let mut evals = vec![F::zero(); domain_size];
+            for (eval, row) in evals.iter_mut().zip(rows) {
+                *eval = evaluate_constraint(row, full_assignment);
+            }
+            evals
+        };
+        let mut a = evaluate(&matrices.a);
+        let mut b = evaluate(&matrices.b);
+        let mut c = evaluate(&matrices.c);
+        // Every public input `x` is bound by an extra `x * 0 = 0` row.
+        a[num_constraints..num_constraints + num_inputs]
+            .copy_from_slice(&full_assignment[..num_inputs]);
+
+        for evals in [&mut a, &mut b, &mut c] {
+            domain.ifft_in_place(evals);
+            coset.fft_in_place(evals);
+        }
+
+        let mut h = domain.mul_polynomials_in_evaluation_domain(&a, &b);
+        for (h, c) in h.iter_mut().zip(c) {
+            *h -= c;
+        }
+        Ok(h)
+    }
+
+    fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+        max_power: usize,
+        t: F,
+        _zt: F,
+        delta_inverse: F,
+    ) -> Result<Vec<F>, SynthesisError> {
+        // `a * b - c` has degree `2 * max_power`. Its value at `t` is the sum
+        // of its values on the doubled domain weighted by the Lagrange
+        // polynomials there, of which only the odd points are nonzero.
+        let mut scalars = (0..=2 * max_power as u64)
+            .map(|power| delta_inverse * t.pow([power]))
+            .collect::<Vec<_>>();
+        let domain = D::new(scalars.len()).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        domain.ifft_in_place(&mut scalars);
+        Ok(scalars.into_iter().skip(1).step_by(2).collect())
+    }
+}
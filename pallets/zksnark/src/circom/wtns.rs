@@ -0,0 +1,42 @@
+//! Reading the `.wtns` files written by `generate_witness.js` and
+//! `snarkjs wtns calculate`.
+//!
+//! A witness holds one value per wire of the circuit's `.r1cs`, in the
+//! same order, starting with the constant one.
+
+use super::{reader::Sections, CircomError};
+use ark_ff::PrimeField;
+
+const MAGIC: &[u8; 4] = b"wtns";
+const VERSION: u32 = 2;
+
+const HEADER: u32 = 1;
+const VALUES: u32 = 2;
+
+/// Parse a `.wtns` file over `F`. Files for another prime are refused.
+pub fn read_witness<F: PrimeField>(bytes: &[u8]) -> Result<Vec<F>, CircomError> {
+    let sections = Sections::read(bytes, MAGIC, VERSION)?;
+
+    let mut header = sections.get(HEADER)?;
+    let n8 = header.prime::<F>()?;
+    let count = header.usize()?;
+
+    let mut values = sections.get(VALUES)?;
+    (0..count).map(|_| values.element(n8)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+
+    #[test]
+    fn reads_the_multiplier_witness() {
+        let witness = read_witness::<Fr>(include_bytes!("../circuits/test/multiplier.wtns"));
+        let expected = [1u64, 102, 11, 3, 9].map(Fr::from);
+        assert_eq!(witness, Ok(expected.to_vec()));
+
+        let r1cs = include_bytes!("../circuits/test/multiplier.r1cs");
+        assert_eq!(read_witness::<Fr>(r1cs), Err(CircomError::WrongFileType));
+    }
+}
@@ -0,0 +1,167 @@
+//! Reading the Groth16 proving keys snarkjs writes as `.zkey` files.
+//!
+//! The points are stored uncompressed with their coordinates in
+//! Montgomery form, `(0, 0)` standing for the point at infinity. The `H`
+//! points are the ones [`super::CircomReduction`] computes the quotient
+//! for, not the ones arkworks' default reduction expects.
+
+use super::{
+    reader::{Reader, Sections},
+    CircomError,
+};
+use crate::curve::checked_point;
+use ark_ec::{
+    pairing::Pairing,
+    short_weierstrass::{Affine, SWCurveConfig},
+};
+use ark_ff::{
+    fields::models::{Fp, MontBackend, MontConfig, QuadExtConfig, QuadExtField},
+    BigInt, Field,
+};
+use ark_groth16::{ProvingKey, VerifyingKey};
+
+const MAGIC: &[u8; 4] = b"zkey";
+const VERSION: u32 = 1;
+
+const HEADER: u32 = 1;
+const GROTH16_HEADER: u32 = 2;
+const IC: u32 = 3;
+const A: u32 = 5;
+const B_G1: u32 = 6;
+const B_G2: u32 = 7;
+const C: u32 = 8;
+const H: u32 = 9;
+
+/// The key type snarkjs writes for Groth16.
+const PROTOCOL_GROTH16: u32 = 1;
+
+/// A field whose elements can be read from a `.zkey`.
+pub trait ZkeyField: Field {
+    /// Read an element whose prime field limbs are `n8` bytes long.
+    fn read(reader: &mut Reader, n8: usize) -> Result<Self, CircomError>;
+}
+
+impl<T: MontConfig<N>, const N: usize> ZkeyField for Fp<MontBackend<T, N>, N> {
+    fn read(reader: &mut Reader, n8: usize) -> Result<Self, CircomError> {
+        if n8 != 8 * N {
+            return Err(CircomError::FieldMismatch);
+        }
+        let bytes = reader.take(n8)?;
+        let limbs = core::array::from_fn(|i| {
+            u64::from_le_bytes(bytes[8 * i..8 * (i + 1)].try_into().expect("8 bytes; qed"))
+        });
+        let montgomery = BigInt(limbs);
+        // snarkjs' Montgomery radix is arkworks' one, so the limbs are kept
+        // as they are. Values `>=` the modulus have no field element.
+        if montgomery >= T::MODULUS {
+            return Err(CircomError::NonCanonicalElement);
+        }
+        Ok(Fp::new_unchecked(montgomery))
+    }
+}
+
+impl<P: QuadExtConfig> ZkeyField for QuadExtField<P>
+where
+    P::BaseField: ZkeyField,
+{
+    fn read(reader: &mut Reader, n8: usize) -> Result<Self, CircomError> {
+        let c0 = P::BaseField::read(reader, n8)?;
+        let c1 = P::BaseField::read(reader, n8)?;
+        Ok(QuadExtField::new(c0, c1))
+    }
+}
+
+/// A curve point that can be read from a `.zkey`.
+pub trait ZkeyPoint: Sized {
+    fn read(reader: &mut Reader, n8: usize) -> Result<Self, CircomError>;
+}
+
+impl<P: SWCurveConfig> ZkeyPoint for Affine<P>
+where
+    P::BaseField: ZkeyField,
+{
+    fn read(reader: &mut Reader, n8: usize) -> Result<Self, CircomError> {
+        let x = P::BaseField::read(reader, n8)?;
+        let y = P::BaseField::read(reader, n8)?;
+        checked_point(x, y).map_err(CircomError::InvalidPoint)
+    }
+}
+
+/// Parse a snarkjs Groth16 `.zkey` for the pairing engine `E`. Keys for
+/// another curve or proving system are refused.
+pub fn read_proving_key<E>(bytes: &[u8]) -> Result<ProvingKey<E>, CircomError>
+where
+    E: Pairing,
+    E::G1Affine: ZkeyPoint,
+    E::G2Affine: ZkeyPoint,
+{
+    let sections = Sections::read(bytes, MAGIC, VERSION)?;
+    if sections.get(HEADER)?.u32()? != PROTOCOL_GROTH16 {
+        return Err(CircomError::UnsupportedProtocol);
+    }
+
+    let mut header = sections.get(GROTH16_HEADER)?;
+    let n8q = header.prime::<E::BaseField>()?;
+    let _n8r = header.prime::<E::ScalarField>()?;
+    let variables = header.usize()?;
+    let public = header.usize()?;
+    let domain_size = header.usize()?;
+    let private = variables.checked_sub(public + 1).ok_or(CircomError::WireOutOfRange)?;
+
+    let alpha_g1 = E::G1Affine::read(&mut header, n8q)?;
+    let beta_g1 = E::G1Affine::read(&mut header, n8q)?;
+    let beta_g2 = E::G2Affine::read(&mut header, n8q)?;
+    let gamma_g2 = E::G2Affine::read(&mut header, n8q)?;
+    let delta_g1 = E::G1Affine::read(&mut header, n8q)?;
+    let delta_g2 = E::G2Affine::read(&mut header, n8q)?;
+
+    let points = |section, count| -> Result<Vec<E::G1Affine>, CircomError> {
+        let mut reader = sections.get(section)?;
+        (0..count).map(|_| E::G1Affine::read(&mut reader, n8q)).collect()
+    };
+    let gamma_abc_g1 = points(IC, public + 1)?;
+    let a_query = points(A, variables)?;
+    let b_g1_query = points(B_G1, variables)?;
+    let l_query = points(C, private)?;
+    let h_query = points(H, domain_size)?;
+
+    let mut reader = sections.get(B_G2)?;
+    let b_g2_query = (0..variables)
+        .map(|_| E::G2Affine::read(&mut reader, n8q))
+        .collect::<Result<_, _>>()?;
+
+    Ok(ProvingKey {
+        vk: VerifyingKey { alpha_g1, beta_g2, gamma_g2, delta_g2, gamma_abc_g1 },
+        beta_g1,
+        delta_g1,
+        a_query,
+        b_g1_query,
+        b_g2_query,
+        h_query,
+        l_query,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Groth16Curve;
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::Bn254;
+
+    const MULTIPLIER: &[u8] = include_bytes!("../circuits/test/multiplier_final.zkey");
+
+    #[test]
+    fn reads_the_key_snarkjs_exports() {
+        let pk = read_proving_key::<Bn254>(MULTIPLIER).unwrap();
+        assert_eq!(pk.a_query.len(), 5);
+        assert_eq!(pk.l_query.len(), 2);
+        let exported = include_bytes!("../circuits/test/multiplier_verification_key.json");
+        assert_eq!(Bn254::parse_verification_key(exported), Ok(pk.vk));
+
+        assert_eq!(
+            read_proving_key::<Bls12_381>(MULTIPLIER).map(|_| ()),
+            Err(CircomError::FieldMismatch)
+        );
+    }
+}
@@ -2,13 +2,16 @@
 
     include "./merkle_tree.circom";
     include "node_modules/circomlib/circuits/poseidon.circom";
-    include "node_modules/circomlib/circuits/mux1.circom";
     include "node_modules/circomlib/circuits/comparators.circom";
+    include "node_modules/circomlib/circuits/bitify.circom";
 
+    // Public signals, in the order the verifier receives them:
+    // nullifierHash, newCommitment, root, maxAmount, recipient.
+    // newCommitment re-commits the spent note, so no value moves and there
+    // is no relayer or fee to bind; join_split.circom carries those.
     template EnhancedTransaction(levels) {
         //  inputs
-        signal input  sender;
-        signal input  receiver;
+        signal input  asset;
         signal input  amount;
         signal input  nullifier;
         signal input  secret;
@@ -18,23 +21,32 @@
         // Public inputs
         signal input root;
         signal input maxAmount;
+        signal input recipient;
 
         // Outputs
         signal output nullifierHash;
         signal output newCommitment;
 
-        // Amount range check (0 <= amount <= maxAmount)
-        signal rangeCheck;
-        rangeCheck <== maxAmount - amount;
+        // Amount range check (0 <= amount <= maxAmount < 2^32). LessEqThan
+        // only compares correctly when both inputs fit in 32 bits, so bound
+        // them first; otherwise a "negative" amount wraps in the field.
+        component amountBits = Num2Bits(32);
+        amountBits.in <== amount;
+        component maxAmountBits = Num2Bits(32);
+        maxAmountBits.in <== maxAmount;
+
         component rangeProof = LessEqThan(32);
         rangeProof.in[0] <== amount;
         rangeProof.in[1] <== maxAmount;
+        rangeProof.out === 1;
 
-        // Calculate commitment
-        component commitmentHasher = Poseidon(3);
-        commitmentHasher.inputs[0] <== amount;
-        commitmentHasher.inputs[1] <== secret;
-        commitmentHasher.inputs[2] <== nullifier;
+        // Calculate commitment. The asset is private: a transfer reveals
+        // neither what is moved nor how much.
+        component commitmentHasher = Poseidon(4);
+        commitmentHasher.inputs[0] <== asset;
+        commitmentHasher.inputs[1] <== amount;
+        commitmentHasher.inputs[2] <== secret;
+        commitmentHasher.inputs[3] <== nullifier;
         newCommitment <== commitmentHasher.out;
 
         // Verify Merkle proof
@@ -51,49 +63,15 @@
         nullifierHasher.inputs[0] <== nullifier;
         nullifierHasher.inputs[1] <== secret;
         nullifierHash <== nullifierHasher.out;
-    }
-
-    // For batch verification
-    template BatchTransaction(levels, batchSize) {
-        // Arrays of inputs for each transaction
-        signal input  senders[batchSize];
-        signal input  receivers[batchSize];
-        signal input  amounts[batchSize];
-        signal input  nullifiers[batchSize];
-        signal input  secrets[batchSize];
-        signal input  merklePathElements[batchSize][levels];
-        signal input  merklePathIndices[batchSize][levels];
-
-        // Public inputs
-        signal input roots[batchSize];
-        signal input maxAmount;
-        signal input  sender;
 
-        // Outputs
-        signal output nullifierHashes[batchSize];
-        signal output newCommitments[batchSize];
-
-        // Process each transaction
-        component transactions[batchSize];
-        for (var i = 0; i < batchSize; i++) {
-            transactions[i] = EnhancedTransaction(levels);
-            
-            transactions[i].sender <== senders[i];
-            transactions[i].receiver <== receivers[i];
-            transactions[i].amount <== amounts[i];
-            transactions[i].nullifier <== nullifiers[i];
-            transactions[i].secret <== secrets[i];
-            transactions[i].root <== roots[i];
-            transactions[i].maxAmount <== maxAmount;
-
-            for (var j = 0; j < levels; j++) {
-                transactions[i].merklePathElements[j] <== merklePathElements[i][j];
-                transactions[i].merklePathIndices[j] <== merklePathIndices[i][j];
-            }
-
-            nullifierHashes[i] <== transactions[i].nullifierHash;
-            newCommitments[i] <== transactions[i].newCommitment;
-        }
+        // recipient takes part in no other constraint. Squaring it keeps the
+        // compiler from optimising it away, so a proof is only valid for the
+        // account it was made out to.
+        signal recipientSquare;
+        recipientSquare <== recipient * recipient;
     }
 
-    component main = BatchTransaction(20, 4); // 20 levels, batch size 4
\ No newline at end of file
+    // Batches are verified by the pallet with one multi-pairing over
+    // independent EnhancedTransaction proofs, so the main component is a
+    // single transaction.
+    component main {public [root, maxAmount, recipient]} = EnhancedTransaction(20);
@@ -0,0 +1,151 @@
+pragma circom 2.0.0;
+
+include "./merkle_tree.circom";
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "node_modules/circomlib/circuits/comparators.circom";
+include "node_modules/circomlib/circuits/bitify.circom";
+
+// Spends nIns notes from the tree and creates nOuts new ones. Notes may
+// hold different assets; value is conserved per asset, with the fee paid
+// in feeAsset. Notes are committed to as
+// Poseidon(asset, amount, secret, nullifier) and spent by revealing
+// Poseidon(nullifier, secret), as in EnhancedTransaction.
+//
+// Public signals, in the order the verifier receives them:
+// inputNullifierHash[0..nIns], outputCommitment[0..nOuts], root, relayer,
+// feeAsset, fee.
+template JoinSplit(levels, nIns, nOuts) {
+    // Notes being spent
+    signal input inAsset[nIns];
+    signal input inAmount[nIns];
+    signal input inSecret[nIns];
+    signal input inNullifier[nIns];
+    signal input inPathElements[nIns][levels];
+    signal input inPathIndices[nIns][levels];
+
+    // Notes being created
+    signal input outAsset[nOuts];
+    signal input outAmount[nOuts];
+    signal input outSecret[nOuts];
+    signal input outNullifier[nOuts];
+
+    // Public inputs
+    signal input root;
+    signal input relayer;
+    signal input feeAsset;
+    signal input fee;
+
+    // Outputs
+    signal output inputNullifierHash[nIns];
+    signal output outputCommitment[nOuts];
+
+    component inAmountBits[nIns];
+    component inCommitmentHasher[nIns];
+    component inNullifierHasher[nIns];
+    component inTree[nIns];
+    component inRootCheck[nIns];
+
+    for (var i = 0; i < nIns; i++) {
+        inAmountBits[i] = Num2Bits(64);
+        inAmountBits[i].in <== inAmount[i];
+
+        inCommitmentHasher[i] = Poseidon(4);
+        inCommitmentHasher[i].inputs[0] <== inAsset[i];
+        inCommitmentHasher[i].inputs[1] <== inAmount[i];
+        inCommitmentHasher[i].inputs[2] <== inSecret[i];
+        inCommitmentHasher[i].inputs[3] <== inNullifier[i];
+
+        inNullifierHasher[i] = Poseidon(2);
+        inNullifierHasher[i].inputs[0] <== inNullifier[i];
+        inNullifierHasher[i].inputs[1] <== inSecret[i];
+        inputNullifierHash[i] <== inNullifierHasher[i].out;
+
+        inTree[i] = MerkleTreeChecker(levels);
+        inTree[i].leaf <== inCommitmentHasher[i].out;
+        for (var j = 0; j < levels; j++) {
+            inTree[i].pathElements[j] <== inPathElements[i][j];
+            inTree[i].pathIndices[j] <== inPathIndices[i][j];
+        }
+
+        // A zero-value input pads a spend of fewer notes and need not be in
+        // the tree. Its nullifier hash is still revealed and recorded.
+        inRootCheck[i] = ForceEqualIfEnabled();
+        inRootCheck[i].in[0] <== root;
+        inRootCheck[i].in[1] <== inTree[i].root;
+        inRootCheck[i].enabled <== inAmount[i];
+    }
+
+    // The same note cannot be spent twice in one proof.
+    component sameNullifier[nIns * (nIns - 1) / 2];
+    var pair = 0;
+    for (var i = 0; i < nIns - 1; i++) {
+        for (var j = i + 1; j < nIns; j++) {
+            sameNullifier[pair] = IsEqual();
+            sameNullifier[pair].in[0] <== inputNullifierHash[i];
+            sameNullifier[pair].in[1] <== inputNullifierHash[j];
+            sameNullifier[pair].out === 0;
+            pair++;
+        }
+    }
+
+    component outAmountBits[nOuts];
+    component outCommitmentHasher[nOuts];
+
+    for (var i = 0; i < nOuts; i++) {
+        outAmountBits[i] = Num2Bits(64);
+        outAmountBits[i].in <== outAmount[i];
+
+        outCommitmentHasher[i] = Poseidon(4);
+        outCommitmentHasher[i].inputs[0] <== outAsset[i];
+        outCommitmentHasher[i].inputs[1] <== outAmount[i];
+        outCommitmentHasher[i].inputs[2] <== outSecret[i];
+        outCommitmentHasher[i].inputs[3] <== outNullifier[i];
+        outputCommitment[i] <== outCommitmentHasher[i].out;
+    }
+
+    component feeBits = Num2Bits(64);
+    feeBits.in <== fee;
+
+    // Value conservation, once for every asset that appears: the inputs of
+    // that asset are worth its outputs, plus the fee if it is paid in it.
+    // Every term is below 2^64, so neither side can wrap the field.
+    var nTerms = nIns + nOuts + 1;
+    component sameAsset[nTerms][nTerms];
+    signal share[nTerms][nTerms];
+    for (var a = 0; a < nTerms; a++) {
+        var balance = 0;
+        for (var t = 0; t < nTerms; t++) {
+            sameAsset[a][t] = IsEqual();
+            if (a < nIns) {
+                sameAsset[a][t].in[0] <== inAsset[a];
+            } else if (a < nIns + nOuts) {
+                sameAsset[a][t].in[0] <== outAsset[a - nIns];
+            } else {
+                sameAsset[a][t].in[0] <== feeAsset;
+            }
+
+            if (t < nIns) {
+                sameAsset[a][t].in[1] <== inAsset[t];
+                share[a][t] <== sameAsset[a][t].out * inAmount[t];
+                balance += share[a][t];
+            } else if (t < nIns + nOuts) {
+                sameAsset[a][t].in[1] <== outAsset[t - nIns];
+                share[a][t] <== sameAsset[a][t].out * outAmount[t - nIns];
+                balance -= share[a][t];
+            } else {
+                sameAsset[a][t].in[1] <== feeAsset;
+                share[a][t] <== sameAsset[a][t].out * fee;
+                balance -= share[a][t];
+            }
+        }
+        balance === 0;
+    }
+
+    // relayer takes part in no other constraint. Squaring it keeps the
+    // compiler from optimising it away, so the fee can only be collected by
+    // the relayer the proof was made out to.
+    signal relayerSquare;
+    relayerSquare <== relayer * relayer;
+}
+
+component main {public [root, relayer, feeAsset, fee]} = JoinSplit(20, 2, 2);
@@ -1,33 +1,45 @@
 pragma circom 2.0.0;
 
 include "node_modules/circomlib/circuits/poseidon.circom";
-include "node_modules/circomlib/circuits/mux1.circom";
 
+// Swaps its inputs when s = 1. s must be boolean.
+template DualMux() {
+    signal input in[2];
+    signal input s;
+    signal output out[2];
+
+    s * (1 - s) === 0;
+    out[0] <== (in[1] - in[0]) * s + in[0];
+    out[1] <== (in[0] - in[1]) * s + in[1];
+}
+
+// Computes the root of a Poseidon Merkle tree from a leaf and its path.
+// pathIndices[i] is 0 when the node at level i is a left child and 1 when
+// it is a right child.
 template MerkleTreeChecker(levels) {
     signal input leaf;
     signal input pathElements[levels];
     signal input pathIndices[levels];
     signal output root;
 
-    component poseidons[levels];
-    component mux[levels];
+    component selectors[levels];
+    component hashers[levels];
 
     signal levelHashes[levels + 1];
     levelHashes[0] <== leaf;
 
     for (var i = 0; i < levels; i++) {
-        poseidons[i] = Poseidon(2);
-        mux[i] = Mux1();
-
-        mux[i].c[0] <== levelHashes[i];
-        mux[i].c[1] <== pathElements[i];
-        mux[i].s <== pathIndices[i];
+        selectors[i] = DualMux();
+        selectors[i].in[0] <== levelHashes[i];
+        selectors[i].in[1] <== pathElements[i];
+        selectors[i].s <== pathIndices[i];
 
-        poseidons[i].inputs[0] <== mux[i].out;
-        poseidons[i].inputs[1] <== mux[i].out;
+        hashers[i] = Poseidon(2);
+        hashers[i].inputs[0] <== selectors[i].out[0];
+        hashers[i].inputs[1] <== selectors[i].out[1];
 
-        levelHashes[i + 1] <== poseidons[i].out;
+        levelHashes[i + 1] <== hashers[i].out;
     }
 
     root <== levelHashes[levels];
-}
\ No newline at end of file
+}
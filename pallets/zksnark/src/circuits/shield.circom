@@ -0,0 +1,34 @@
+pragma circom 2.0.0;
+
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "node_modules/circomlib/circuits/bitify.circom";
+
+// Proves that a new note commits to a public asset and amount, so the
+// pallet can take exactly that amount of that asset from the depositor
+// before adding the note to the tree. The note is
+// Poseidon(asset, amount, secret, nullifier), as spent by
+// EnhancedTransaction and JoinSplit.
+//
+// Public signals, in the order the verifier receives them:
+// commitment, asset, amount.
+template Shield() {
+    signal input asset;
+    signal input amount;
+    signal input secret;
+    signal input nullifier;
+
+    signal output commitment;
+
+    // Notes carry 64-bit amounts.
+    component amountBits = Num2Bits(64);
+    amountBits.in <== amount;
+
+    component commitmentHasher = Poseidon(4);
+    commitmentHasher.inputs[0] <== asset;
+    commitmentHasher.inputs[1] <== amount;
+    commitmentHasher.inputs[2] <== secret;
+    commitmentHasher.inputs[3] <== nullifier;
+    commitment <== commitmentHasher.out;
+}
+
+component main {public [asset, amount]} = Shield();
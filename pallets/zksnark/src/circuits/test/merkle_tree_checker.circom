@@ -0,0 +1,30 @@
+pragma circom 2.0.0;
+
+// Checks the entries of merkle_tree_vectors.json against MerkleTreeChecker.
+// Every object in its "proofs" and "tampered" arrays is a valid input for
+// this circuit:
+//
+//   circom merkle_tree_checker.circom --wasm -l ..
+//   node merkle_tree_checker_js/generate_witness.js \
+//       merkle_tree_checker_js/merkle_tree_checker.wasm proof.json witness.wtns
+//
+// Witness generation fails if the path does not hash to the root.
+
+include "../merkle_tree.circom";
+
+template MerkleTreeCheckerTest(levels) {
+    signal input leaf;
+    signal input root;
+    signal input pathElements[levels];
+    signal input pathIndices[levels];
+
+    component checker = MerkleTreeChecker(levels);
+    checker.leaf <== leaf;
+    for (var i = 0; i < levels; i++) {
+        checker.pathElements[i] <== pathElements[i];
+        checker.pathIndices[i] <== pathIndices[i];
+    }
+    checker.root === root;
+}
+
+component main {public [root]} = MerkleTreeCheckerTest(20);
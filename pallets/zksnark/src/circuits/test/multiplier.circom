@@ -0,0 +1,25 @@
+// The circuit behind the multiplier fixtures read by `crate::circom`. It
+// is written for the circom 0.5 compiler in node_modules, which keeps the
+// fixtures small enough to check in:
+//
+//   circom multiplier.circom --r1cs multiplier.r1cs --wasm multiplier.wasm
+//   snarkjs groth16 setup multiplier.r1cs pot12_final.ptau multiplier_0000.zkey
+//   snarkjs zkey contribute multiplier_0000.zkey multiplier_final.zkey
+//   snarkjs zkey export verificationkey multiplier_final.zkey \
+//       multiplier_verification_key.json
+//   echo '{"a": "3", "b": "11"}' > input.json
+//   snarkjs wtns calculate multiplier.wasm input.json multiplier.wtns
+//
+// The witness is [1, c, b, a, d] = [1, 102, 11, 3, 9].
+
+template Multiplier() {
+    signal private input a;
+    signal input b;
+    signal output c;
+    signal d;
+
+    d <== a * a;
+    c <== d * b + a;
+}
+
+component main = Multiplier();
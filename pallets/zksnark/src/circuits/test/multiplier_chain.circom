@@ -0,0 +1,29 @@
+// The circuit behind multiplier_chain.wasm, which `crate::circom` computes
+// witnesses with. The wasm is the circom 2 build circom_runtime tests its
+// witness calculator with, node_modules/snarkjs/node_modules/circom_runtime/
+// test/circuit/circuit_js/circuit.wasm, with this source:
+//
+//   circom multiplier_chain.circom --wasm
+//
+// The witness is [1, d, a, b, c, int[0], ..., int[998]]: int[999] is the
+// same wire as d.
+
+pragma circom 2.0.0;
+
+template Multiplier(n) {
+    signal input a;
+    signal input b;
+    signal input c;
+    signal output d;
+   
+    signal int[n];
+
+    int[0] <== a * a + b * 2 + c + 3;
+    for (var i = 1; i < n; i++) {
+	    int[i] <== int[i-1] * int[i-1] + b + 4;
+    }
+
+    d <== int[n-1];
+}
+
+component main {public [a, b, c]} = Multiplier(1000);
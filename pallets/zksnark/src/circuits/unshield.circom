@@ -0,0 +1,71 @@
+pragma circom 2.0.0;
+
+include "./merkle_tree.circom";
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "node_modules/circomlib/circuits/comparators.circom";
+include "node_modules/circomlib/circuits/bitify.circom";
+
+// Spends a note from the tree to a transparent account. The asset and
+// amount are public so the pallet can pay them out of the pool:
+// amount - fee to the recipient and fee to the relayer that submitted the
+// proof, both in the note's asset.
+//
+// Public signals, in the order the verifier receives them:
+// nullifierHash, root, asset, amount, recipient, relayer, fee.
+template Unshield(levels) {
+    signal input secret;
+    signal input nullifier;
+    signal input pathElements[levels];
+    signal input pathIndices[levels];
+
+    // Public inputs
+    signal input root;
+    signal input asset;
+    signal input amount;
+    signal input recipient;
+    signal input relayer;
+    signal input fee;
+
+    signal output nullifierHash;
+
+    // Notes carry 64-bit amounts, and the fee comes out of the note
+    // (0 <= fee <= amount).
+    component amountBits = Num2Bits(64);
+    amountBits.in <== amount;
+    component feeBits = Num2Bits(64);
+    feeBits.in <== fee;
+
+    component feeCheck = LessEqThan(64);
+    feeCheck.in[0] <== fee;
+    feeCheck.in[1] <== amount;
+    feeCheck.out === 1;
+
+    component commitmentHasher = Poseidon(4);
+    commitmentHasher.inputs[0] <== asset;
+    commitmentHasher.inputs[1] <== amount;
+    commitmentHasher.inputs[2] <== secret;
+    commitmentHasher.inputs[3] <== nullifier;
+
+    component tree = MerkleTreeChecker(levels);
+    tree.leaf <== commitmentHasher.out;
+    for (var i = 0; i < levels; i++) {
+        tree.pathElements[i] <== pathElements[i];
+        tree.pathIndices[i] <== pathIndices[i];
+    }
+    tree.root === root;
+
+    component nullifierHasher = Poseidon(2);
+    nullifierHasher.inputs[0] <== nullifier;
+    nullifierHasher.inputs[1] <== secret;
+    nullifierHash <== nullifierHasher.out;
+
+    // recipient and relayer take part in no other constraint. Squaring
+    // them keeps the compiler from optimising them away, so the payout
+    // cannot be redirected by whoever submits the proof.
+    signal recipientSquare;
+    recipientSquare <== recipient * recipient;
+    signal relayerSquare;
+    relayerSquare <== relayer * relayer;
+}
+
+component main {public [root, asset, amount, recipient, relayer, fee]} = Unshield(20);
@@ -0,0 +1,312 @@
+//! Pairing engines the verifier can run Groth16 over.
+//!
+//! circom compiles our circuits over bn128 (BN254) by default, while the
+//! pallet originally targeted BLS12-381. Each supported engine implements
+//! [`Groth16Curve`], and the engine for a given key is picked from the
+//! key's `curve` field.
+
+use crate::{
+    encoding::{self, ProofError},
+    vk::{self, VerificationKeyError},
+    Proof,
+};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::{
+    pairing::Pairing,
+    short_weierstrass::{Affine, SWCurveConfig},
+};
+use ark_ff::{
+    fields::models::{QuadExtConfig, QuadExtField},
+    BigInteger, PrimeField, Zero,
+};
+use ark_groth16::VerifyingKey;
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// Identifies the pairing engine a verifying key belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub enum Curve {
+    /// BN254, called `bn128` by circom and snarkjs.
+    Bn254,
+    /// BLS12-381, called `bls12381` by snarkjs.
+    Bls12_381,
+}
+
+impl Curve {
+    /// The name snarkjs writes in the `curve` field of its JSON files.
+    pub fn snarkjs_name(&self) -> &'static str {
+        match self {
+            Curve::Bn254 => "bn128",
+            Curve::Bls12_381 => "bls12381",
+        }
+    }
+
+    /// Look up a curve by its snarkjs name.
+    pub fn from_snarkjs_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("bn128") || name.eq_ignore_ascii_case("bn254") {
+            Some(Curve::Bn254)
+        } else if name.eq_ignore_ascii_case("bls12381") || name.eq_ignore_ascii_case("bls12-381") {
+            Some(Curve::Bls12_381)
+        } else {
+            None
+        }
+    }
+
+    /// Read the `curve` field of a snarkjs verification key.
+    pub fn of_snarkjs_key(vk_bytes: &[u8]) -> Result<Self, VerificationKeyError> {
+        let value: serde_json::Value =
+            serde_json::from_slice(vk_bytes).map_err(|_| VerificationKeyError::InvalidJson)?;
+        let name = value["curve"]
+            .as_str()
+            .ok_or(VerificationKeyError::MissingField("curve"))?;
+        Self::from_snarkjs_name(name).ok_or(VerificationKeyError::CurveMismatch)
+    }
+}
+
+/// Reasons an encoded proof point can be rejected.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum PointError {
+    /// The encoding has the wrong number of bytes for the curve.
+    InvalidLength,
+    /// A coordinate is not smaller than the base field modulus.
+    NonCanonicalCoordinate,
+    /// The coordinates do not satisfy the curve equation.
+    NotOnCurve,
+    /// The point is on the curve but outside the prime-order subgroup.
+    NotInSubgroup,
+    /// A compressed encoding has invalid flags or an `x` with no matching `y`.
+    InvalidEncoding,
+}
+
+/// A pairing engine proofs can be verified over.
+pub trait Groth16Curve: Pairing {
+    /// The tag for this engine.
+    const CURVE: Curve;
+
+    /// Decode an uncompressed big-endian `x || y` G1 point. All-zero bytes
+    /// decode to the point at infinity.
+    fn decode_g1(bytes: &[u8]) -> Result<Self::G1Affine, PointError>;
+
+    /// Decode an uncompressed big-endian `x.c0 || x.c1 || y.c0 || y.c1` G2 point.
+    fn decode_g2(bytes: &[u8]) -> Result<Self::G2Affine, PointError>;
+
+    /// Decode the points of `proof` according to its encoding tag.
+    fn decode_proof(proof: &Proof) -> Result<ark_groth16::Proof<Self>, ProofError>;
+
+    /// Parse a snarkjs `verification_key.json` for this engine.
+    fn parse_verification_key(vk_bytes: &[u8]) -> Result<VerifyingKey<Self>, VerificationKeyError>;
+
+    /// Write `vk` as a snarkjs `verification_key.json`, the format
+    /// `register_verifying_key` takes.
+    fn export_verification_key(vk: &VerifyingKey<Self>) -> Vec<u8>;
+}
+
+impl Groth16Curve for Bn254 {
+    const CURVE: Curve = Curve::Bn254;
+
+    fn decode_g1(bytes: &[u8]) -> Result<Self::G1Affine, PointError> {
+        decode_g1(bytes)
+    }
+
+    fn decode_g2(bytes: &[u8]) -> Result<Self::G2Affine, PointError> {
+        decode_g2(bytes)
+    }
+
+    fn decode_proof(proof: &Proof) -> Result<ark_groth16::Proof<Self>, ProofError> {
+        encoding::decode_proof::<Self>(proof)
+    }
+
+    fn parse_verification_key(vk_bytes: &[u8]) -> Result<VerifyingKey<Self>, VerificationKeyError> {
+        vk::parse_snarkjs::<Self>(vk_bytes, Self::CURVE.snarkjs_name())
+    }
+
+    fn export_verification_key(vk: &VerifyingKey<Self>) -> Vec<u8> {
+        vk::to_snarkjs(vk, Self::CURVE.snarkjs_name())
+    }
+}
+
+impl Groth16Curve for Bls12_381 {
+    const CURVE: Curve = Curve::Bls12_381;
+
+    fn decode_g1(bytes: &[u8]) -> Result<Self::G1Affine, PointError> {
+        decode_g1(bytes)
+    }
+
+    fn decode_g2(bytes: &[u8]) -> Result<Self::G2Affine, PointError> {
+        decode_g2(bytes)
+    }
+
+    fn decode_proof(proof: &Proof) -> Result<ark_groth16::Proof<Self>, ProofError> {
+        encoding::decode_proof::<Self>(proof)
+    }
+
+    fn parse_verification_key(vk_bytes: &[u8]) -> Result<VerifyingKey<Self>, VerificationKeyError> {
+        vk::parse_snarkjs::<Self>(vk_bytes, Self::CURVE.snarkjs_name())
+    }
+
+    fn export_verification_key(vk: &VerifyingKey<Self>) -> Vec<u8> {
+        vk::to_snarkjs(vk, Self::CURVE.snarkjs_name())
+    }
+}
+
+/// Size in bytes of a big-endian encoded element of `F`.
+pub fn field_size<F: PrimeField>() -> usize {
+    F::MODULUS_BIT_SIZE.div_ceil(8) as usize
+}
+
+/// Decode a big-endian field element, refusing values `>=` the modulus so
+/// that every element has exactly one accepted encoding.
+pub fn decode_canonical<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let element = F::from_be_bytes_mod_order(bytes);
+    let canonical = element.into_bigint().to_bytes_be();
+    let (padding, value) = canonical.split_at(canonical.len().saturating_sub(bytes.len()));
+    if value != bytes || padding.iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(element)
+}
+
+fn decode_coordinate<F: PrimeField>(bytes: &[u8]) -> Result<F, PointError> {
+    decode_canonical(bytes).ok_or(PointError::NonCanonicalCoordinate)
+}
+
+/// Build an affine point from decoded coordinates, checking it is on the
+/// curve and in the prime-order subgroup. `(0, 0)` is not on either
+/// supported curve and is used as the encoding of the point at infinity.
+pub(crate) fn checked_point<P: SWCurveConfig>(x: P::BaseField, y: P::BaseField) -> Result<Affine<P>, PointError> {
+    if x.is_zero() && y.is_zero() {
+        return Ok(Affine::identity());
+    }
+
+    let point = Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(PointError::NotOnCurve);
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(PointError::NotInSubgroup);
+    }
+    Ok(point)
+}
+
+fn decode_g1<P: SWCurveConfig>(bytes: &[u8]) -> Result<Affine<P>, PointError>
+where
+    P::BaseField: PrimeField,
+{
+    let n = field_size::<P::BaseField>();
+    if bytes.len() != 2 * n {
+        return Err(PointError::InvalidLength);
+    }
+
+    let x = decode_coordinate(&bytes[..n])?;
+    let y = decode_coordinate(&bytes[n..])?;
+
+    checked_point(x, y)
+}
+
+fn decode_g2<P, Q>(bytes: &[u8]) -> Result<Affine<P>, PointError>
+where
+    P: SWCurveConfig<BaseField = QuadExtField<Q>>,
+    Q: QuadExtConfig,
+    Q::BaseField: PrimeField,
+{
+    let n = field_size::<Q::BaseField>();
+    if bytes.len() != 4 * n {
+        return Err(PointError::InvalidLength);
+    }
+
+    let coordinate = |i: usize| decode_coordinate::<Q::BaseField>(&bytes[i * n..(i + 1) * n]);
+    let x = QuadExtField::new(coordinate(0)?, coordinate(1)?);
+    let y = QuadExtField::new(coordinate(2)?, coordinate(3)?);
+
+    checked_point(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vk::SnarkjsPoint;
+    use ark_ec::AffineRepr;
+    use ark_ff::One;
+
+    fn be<F: PrimeField>(element: F) -> Vec<u8> {
+        element.into_bigint().to_bytes_be()
+    }
+
+    fn g1_bytes<P: SWCurveConfig>(point: Affine<P>) -> Vec<u8>
+    where
+        P::BaseField: PrimeField,
+    {
+        [be(point.x), be(point.y)].concat()
+    }
+
+    #[test]
+    fn decodes_valid_points_and_the_identity() {
+        let g1 = ark_bn254::G1Affine::generator();
+        assert_eq!(Bn254::decode_g1(&g1_bytes(g1)), Ok(g1));
+        assert_eq!(Bn254::decode_g1(&[0; 64]), Ok(ark_bn254::G1Affine::identity()));
+
+        let g2 = ark_bn254::G2Affine::generator();
+        let bytes = [be(g2.x.c0), be(g2.x.c1), be(g2.y.c0), be(g2.y.c1)].concat();
+        assert_eq!(Bn254::decode_g2(&bytes), Ok(g2));
+        assert_eq!(Bn254::decode_g2(&bytes[1..]), Err(PointError::InvalidLength));
+    }
+
+    #[test]
+    fn rejects_coordinates_not_below_the_modulus() {
+        let g1 = ark_bn254::G1Affine::generator();
+        let modulus = <ark_bn254::Fq as PrimeField>::MODULUS.to_bytes_be();
+        let bytes = [modulus, be(g1.y)].concat();
+        assert_eq!(Bn254::decode_g1(&bytes), Err(PointError::NonCanonicalCoordinate));
+
+        // `x + p` reduces to the generator's `x` but is a second encoding of it.
+        let mut x = <ark_bn254::Fq as PrimeField>::MODULUS;
+        x.add_with_carry(&g1.x.into_bigint());
+        let bytes = [x.to_bytes_be(), be(g1.y)].concat();
+        assert_eq!(Bn254::decode_g1(&bytes), Err(PointError::NonCanonicalCoordinate));
+    }
+
+    #[test]
+    fn rejects_points_off_the_curve() {
+        let mut g1 = ark_bn254::G1Affine::generator();
+        g1.y += ark_bn254::Fq::one();
+        assert_eq!(Bn254::decode_g1(&g1_bytes(g1)), Err(PointError::NotOnCurve));
+
+        let mut g1 = ark_bls12_381::G1Affine::generator();
+        g1.x += ark_bls12_381::Fq::one();
+        assert_eq!(Bls12_381::decode_g1(&g1_bytes(g1)), Err(PointError::NotOnCurve));
+    }
+
+    #[test]
+    fn rejects_points_outside_the_subgroup() {
+        // BLS12-381's G1 has a cofactor, so most points on the curve are
+        // outside the prime-order subgroup.
+        let point = (1u64..)
+            .map(ark_bls12_381::Fq::from)
+            .filter_map(|x| ark_bls12_381::G1Affine::get_point_from_x_unchecked(x, true))
+            .find(|point| !point.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap();
+        assert!(point.is_on_curve());
+        assert_eq!(Bls12_381::decode_g1(&g1_bytes(point)), Err(PointError::NotInSubgroup));
+    }
+
+    #[test]
+    fn rejects_snarkjs_points_that_are_not_normalized() {
+        let g1 = ark_bn254::G1Affine::generator();
+        let mut json = g1.to_json();
+        assert_eq!(ark_bn254::G1Affine::from_json(&json), Ok(g1));
+
+        json[2] = "2".into();
+        assert_eq!(
+            ark_bn254::G1Affine::from_json(&json),
+            Err(VerificationKeyError::NotNormalized)
+        );
+        json[2] = "0".into();
+        assert_eq!(
+            ark_bn254::G1Affine::from_json(&json),
+            Err(VerificationKeyError::NotNormalized)
+        );
+    }
+}
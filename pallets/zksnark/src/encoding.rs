@@ -0,0 +1,193 @@
+//! Wire formats accepted for the `a`, `b` and `c` elements of a [`Proof`].
+
+use crate::{
+    curve::{Groth16Curve, PointError},
+    vk::{SnarkjsPoint, VerificationKeyError, PROTOCOL_GROTH16},
+    Proof,
+};
+use ark_ec::AffineRepr;
+use ark_serialize::{Compress, Validate};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde_json::Value;
+use sp_runtime::RuntimeDebug;
+
+/// How the points of a [`Proof`] are encoded.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum ProofEncoding {
+    /// Big-endian affine coordinates: `x || y` for G1 and
+    /// `x.c0 || x.c1 || y.c0 || y.c1` for G2 (64/128 bytes on BN254,
+    /// 96/192 bytes on BLS12-381).
+    #[default]
+    Uncompressed,
+    /// arkworks `CanonicalSerialize` compressed points (32/64 bytes on
+    /// BN254, 48/96 bytes on BLS12-381).
+    Compressed,
+    /// The `proof.json` written by `snarkjs groth16 prove`, carried whole in
+    /// `a`. `b` and `c` must be empty.
+    SnarkjsJson,
+}
+
+/// Reasons the points of a [`Proof`] can be rejected.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum ProofError {
+    /// `a` or `c` is not a valid G1 point.
+    G1(PointError),
+    /// `b` is not a valid G2 point.
+    G2(PointError),
+    /// A snarkjs `proof.json` is malformed or for another protocol.
+    InvalidJson,
+    /// A snarkjs `proof.json` names a different curve than the key.
+    CurveMismatch,
+    /// `b` or `c` is non-empty for an encoding that carries the whole proof in `a`.
+    UnexpectedData,
+}
+
+/// Decode the points of `proof` according to its [`ProofEncoding`].
+pub fn decode_proof<E: Groth16Curve>(proof: &Proof) -> Result<ark_groth16::Proof<E>, ProofError>
+where
+    E::G1Affine: SnarkjsPoint,
+    E::G2Affine: SnarkjsPoint,
+{
+    match proof.encoding {
+        ProofEncoding::Uncompressed => Ok(ark_groth16::Proof {
+            a: E::decode_g1(&proof.a).map_err(ProofError::G1)?,
+            b: E::decode_g2(&proof.b).map_err(ProofError::G2)?,
+            c: E::decode_g1(&proof.c).map_err(ProofError::G1)?,
+        }),
+        ProofEncoding::Compressed => Ok(ark_groth16::Proof {
+            a: decode_compressed(&proof.a).map_err(ProofError::G1)?,
+            b: decode_compressed(&proof.b).map_err(ProofError::G2)?,
+            c: decode_compressed(&proof.c).map_err(ProofError::G1)?,
+        }),
+        ProofEncoding::SnarkjsJson => {
+            if !proof.b.is_empty() || !proof.c.is_empty() {
+                return Err(ProofError::UnexpectedData);
+            }
+            decode_snarkjs::<E>(&proof.a)
+        },
+    }
+}
+
+/// Decode an arkworks compressed point. Decompression always yields a point
+/// on the curve, so the only remaining check is subgroup membership.
+fn decode_compressed<A: AffineRepr>(bytes: &[u8]) -> Result<A, PointError> {
+    if bytes.len() != A::zero().compressed_size() {
+        return Err(PointError::InvalidLength);
+    }
+    let point = A::deserialize_with_mode(bytes, Compress::Yes, Validate::No)
+        .map_err(|_| PointError::InvalidEncoding)?;
+    point.check().map_err(|_| PointError::NotInSubgroup)?;
+    Ok(point)
+}
+
+fn decode_snarkjs<E: Groth16Curve>(bytes: &[u8]) -> Result<ark_groth16::Proof<E>, ProofError>
+where
+    E::G1Affine: SnarkjsPoint,
+    E::G2Affine: SnarkjsPoint,
+{
+    let json: Value = serde_json::from_slice(bytes).map_err(|_| ProofError::InvalidJson)?;
+
+    // Both fields are optional in hand-written files, but must agree when present.
+    if let Some(protocol) = json.get("protocol") {
+        if protocol.as_str() != Some(PROTOCOL_GROTH16) {
+            return Err(ProofError::InvalidJson);
+        }
+    }
+    if let Some(curve) = json.get("curve") {
+        let name = curve.as_str().ok_or(ProofError::InvalidJson)?;
+        if !name.eq_ignore_ascii_case(E::CURVE.snarkjs_name()) {
+            return Err(ProofError::CurveMismatch);
+        }
+    }
+
+    let point = |name: &str| json.get(name).ok_or(ProofError::InvalidJson);
+    Ok(ark_groth16::Proof {
+        a: E::G1Affine::from_json(point("pi_a")?).map_err(|e| json_error(e, ProofError::G1))?,
+        b: E::G2Affine::from_json(point("pi_b")?).map_err(|e| json_error(e, ProofError::G2))?,
+        c: E::G1Affine::from_json(point("pi_c")?).map_err(|e| json_error(e, ProofError::G1))?,
+    })
+}
+
+fn json_error(error: VerificationKeyError, point: fn(PointError) -> ProofError) -> ProofError {
+    match error {
+        VerificationKeyError::InvalidPoint(e) => point(e),
+        VerificationKeyError::InvalidFieldElement => point(PointError::NonCanonicalCoordinate),
+        VerificationKeyError::NotNormalized => point(PointError::InvalidEncoding),
+        _ => ProofError::InvalidJson,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_ec::CurveGroup;
+    use ark_serialize::CanonicalSerialize;
+
+    fn groth16_proof() -> ark_groth16::Proof<Bn254> {
+        ark_groth16::Proof {
+            a: (G1Affine::generator() * Fr::from(3u64)).into_affine(),
+            b: (G2Affine::generator() * Fr::from(5u64)).into_affine(),
+            c: (G1Affine::generator() * Fr::from(7u64)).into_affine(),
+        }
+    }
+
+    fn proof(encoding: ProofEncoding, a: Vec<u8>, b: Vec<u8>, c: Vec<u8>) -> Proof {
+        Proof { circuit_id: 0, a, b, c, encoding }
+    }
+
+    fn compressed<P: CanonicalSerialize>(point: &P) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        point.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn snarkjs_json(expected: &ark_groth16::Proof<Bn254>, curve: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "pi_a": expected.a.to_json(),
+            "pi_b": expected.b.to_json(),
+            "pi_c": expected.c.to_json(),
+            "protocol": "groth16",
+            "curve": curve,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn compressed_proofs_round_trip() {
+        let expected = groth16_proof();
+        let encoded = proof(
+            ProofEncoding::Compressed,
+            compressed(&expected.a),
+            compressed(&expected.b),
+            compressed(&expected.c),
+        );
+        assert_eq!(decode_proof::<Bn254>(&encoded), Ok(expected.clone()));
+
+        // A compressed G1 point where a G2 point belongs has the wrong length.
+        let swapped = proof(
+            ProofEncoding::Compressed,
+            compressed(&expected.a),
+            compressed(&expected.a),
+            compressed(&expected.c),
+        );
+        assert_eq!(
+            decode_proof::<Bn254>(&swapped),
+            Err(ProofError::G2(PointError::InvalidLength))
+        );
+    }
+
+    #[test]
+    fn snarkjs_proofs_round_trip() {
+        let expected = groth16_proof();
+        let json = |curve| {
+            proof(ProofEncoding::SnarkjsJson, snarkjs_json(&expected, curve), vec![], vec![])
+        };
+        assert_eq!(decode_proof::<Bn254>(&json("bn128")), Ok(expected.clone()));
+        assert_eq!(decode_proof::<Bn254>(&json("bls12381")), Err(ProofError::CurveMismatch));
+
+        let trailing = Proof { c: vec![0], ..json("bn128") };
+        assert_eq!(decode_proof::<Bn254>(&trailing), Err(ProofError::UnexpectedData));
+    }
+}
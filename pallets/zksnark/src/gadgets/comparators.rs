@@ -0,0 +1,43 @@
+//! circomlib's `Num2Bits`, `LessThan` and `LessEqThan`.
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, eq::EqGadget, fields::fp::FpVar, R1CSVar};
+use ark_relations::r1cs::SynthesisError;
+
+/// `Num2Bits(n)`: the low `n` bits of `value`, least significant first.
+/// Constrains `value < 2^n`.
+pub fn num2bits<F: PrimeField>(
+    value: &FpVar<F>,
+    n: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    let cs = value.cs();
+    let bits = value.value().map(|value| value.into_bigint().to_bits_le());
+    let bit = |i: usize| bits.as_ref().map(|bits| bits[i]).map_err(|e| *e);
+    let bits = (0..n)
+        .map(|i| Boolean::new_witness(cs.clone(), || bit(i)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp(&bits)?.enforce_equal(value)?;
+    Ok(bits)
+}
+
+/// `LessThan(n)`: whether `a < b`. Only meaningful when both are below
+/// `2^n`, which the caller has to constrain.
+pub fn less_than<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    n: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    let offset = F::from(2u64).pow([n as u64]);
+    let bits = num2bits(&(a + offset - b), n + 1)?;
+    Ok(!&bits[n])
+}
+
+/// `LessEqThan(n)`: whether `a <= b`, under the same bounds as
+/// [`less_than`].
+pub fn less_eq_than<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    n: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    less_than(a, &(b + F::one()), n)
+}
@@ -0,0 +1,252 @@
+//! `EnhancedTransaction` from `circuits/enhanced_transaction.circom`.
+
+use super::{
+    comparators::{less_eq_than, num2bits},
+    merkle_tree::merkle_tree_checker,
+    poseidon,
+};
+use crate::{
+    poseidon::Poseidon,
+    utils::{
+        join_split::{MerklePath, Note, WitnessError},
+        merkle::{MerkleTree, NodeStore},
+    },
+};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, eq::EqGadget, fields::fp::FpVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+/// Bit width of `amount` and `maxAmount`.
+const AMOUNT_BITS: usize = 32;
+
+/// Every signal of `EnhancedTransaction(levels)` that the prover supplies.
+///
+/// The spent note's commitment is both checked against the tree and
+/// exposed as `newCommitment`, as in the circom template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnhancedTransaction {
+    pub note: Note,
+    pub path: MerklePath,
+    pub root: Fr,
+    pub max_amount: u64,
+    /// The recipient, as [`crate::transfer::account_input`] maps it.
+    pub recipient: Fr,
+}
+
+impl EnhancedTransaction {
+    /// A circuit over a tree of depth `levels` with every value zero, to set
+    /// keys up from.
+    pub fn blank(levels: usize) -> Self {
+        let zero = Note { asset: Fr::zero(), amount: 0, secret: Fr::zero(), nullifier: Fr::zero() };
+        EnhancedTransaction {
+            note: zero,
+            path: MerklePath { elements: vec![Fr::zero(); levels], indices: vec![false; levels] },
+            root: Fr::zero(),
+            max_amount: 0,
+            recipient: Fr::zero(),
+        }
+    }
+
+    /// Spend `note`, stored at `leaf_index` of `tree`, to `recipient`.
+    /// Checks the ranges the circuit enforces, so a witness that builds also
+    /// proves.
+    ///
+    /// The store must keep every node so that the path can be read from it.
+    pub fn spend<S: NodeStore>(
+        tree: &MerkleTree<Poseidon, S>,
+        note: Note,
+        leaf_index: u32,
+        max_amount: u64,
+        recipient: Fr,
+    ) -> Result<Self, WitnessError> {
+        if max_amount >> AMOUNT_BITS != 0 || note.amount > max_amount {
+            return Err(WitnessError::OutOfRange);
+        }
+
+        Ok(EnhancedTransaction {
+            note,
+            path: MerklePath::of_note(tree, &note, leaf_index)?,
+            root: Fr::from_be_bytes_mod_order(tree.root().as_bytes()),
+            max_amount,
+            recipient,
+        })
+    }
+
+    /// The circuit's public signals, in the order the verifier takes them.
+    pub fn public_signals(&self) -> Vec<Fr> {
+        vec![
+            self.note.nullifier_hash(),
+            self.note.commitment(),
+            self.root,
+            Fr::from(self.max_amount),
+            self.recipient,
+        ]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for EnhancedTransaction {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let witness = |value: Fr| FpVar::new_witness(cs.clone(), || Ok(value));
+        let asset = witness(self.note.asset)?;
+        let amount = witness(Fr::from(self.note.amount))?;
+        let nullifier = witness(self.note.nullifier)?;
+        let secret = witness(self.note.secret)?;
+        let path_elements = self
+            .path
+            .elements
+            .iter()
+            .map(|element| witness(*element))
+            .collect::<Result<Vec<_>, _>>()?;
+        let path_indices = self
+            .path
+            .indices
+            .iter()
+            .map(|index| Boolean::new_witness(cs.clone(), || Ok(*index)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Public signals, in the order circom exposes them.
+        let input = |value: Fr| FpVar::new_input(cs.clone(), || Ok(value));
+        let nullifier_hash = input(self.note.nullifier_hash())?;
+        let new_commitment = input(self.note.commitment())?;
+        let root = input(self.root)?;
+        let max_amount = input(Fr::from(self.max_amount))?;
+        let _recipient = input(self.recipient)?;
+
+        num2bits(&amount, AMOUNT_BITS)?;
+        num2bits(&max_amount, AMOUNT_BITS)?;
+        less_eq_than(&amount, &max_amount, AMOUNT_BITS)?.enforce_equal(&Boolean::TRUE)?;
+
+        let commitment = poseidon::hash4(&asset, &amount, &secret, &nullifier)?;
+        commitment.enforce_equal(&new_commitment)?;
+        merkle_tree_checker(&commitment, &path_elements, &path_indices)?.enforce_equal(&root)?;
+
+        poseidon::hash2(&nullifier, &secret)?.enforce_equal(&nullifier_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::merkle::MemoryStore;
+    use ark_relations::r1cs::ConstraintSystem;
+    use core::str::FromStr;
+    use serde_json::Value;
+
+    /// The vectors `MerkleTreeChecker` is checked against, which also hold
+    /// the nullifier hash of every note.
+    const VECTORS: &str = include_str!("../circuits/test/merkle_tree_vectors.json");
+
+    const MAX_AMOUNT: u64 = 1_000;
+
+    fn field(value: &Value) -> Fr {
+        let decimal = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        Fr::from_str(&decimal).expect("vector holds a decimal field element")
+    }
+
+    /// The notes of the vectors and the tree holding them.
+    fn vectors() -> (Vec<Note>, MerkleTree<Poseidon, MemoryStore>, Value) {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let depth = vectors["levels"].as_u64().unwrap() as u32;
+        let notes: Vec<Note> = vectors["notes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|note| Note {
+                asset: field(&note["asset"]),
+                amount: note["amount"].as_u64().unwrap(),
+                secret: field(&note["secret"]),
+                nullifier: field(&note["nullifier"]),
+            })
+            .collect();
+
+        let mut tree = MerkleTree::new(depth, MemoryStore::default()).unwrap();
+        for note in &notes {
+            tree.insert(crate::poseidon::to_h256(note.commitment())).unwrap();
+        }
+        (notes, tree, vectors)
+    }
+
+    fn spend(
+        tree: &MerkleTree<Poseidon, MemoryStore>,
+        note: Note,
+        index: usize,
+    ) -> EnhancedTransaction {
+        EnhancedTransaction::spend(tree, note, index as u32, MAX_AMOUNT, Fr::from(2u64)).unwrap()
+    }
+
+    /// Synthesize `circuit` and return its public inputs if it is satisfied.
+    fn synthesize(circuit: EnhancedTransaction) -> Option<Vec<Fr>> {
+        let cs = ConstraintSystem::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        if !cs.is_satisfied().unwrap() {
+            return None;
+        }
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        Some(instance[1..].to_vec())
+    }
+
+    #[test]
+    fn witnesses_from_the_tree_satisfy_the_circuit() {
+        let (notes, tree, _) = vectors();
+        for (index, note) in notes.into_iter().enumerate() {
+            let circuit = spend(&tree, note, index);
+            let public_signals = circuit.public_signals();
+            assert_eq!(synthesize(circuit), Some(public_signals));
+        }
+    }
+
+    #[test]
+    fn outputs_match_the_circom_vectors() {
+        let (notes, tree, vectors) = vectors();
+        let expected = vectors["notes"].as_array().unwrap().iter();
+        let proofs = vectors["proofs"].as_array().unwrap();
+        for (index, (note, proof)) in expected.zip(proofs).enumerate() {
+            let circuit = spend(&tree, notes[index], index);
+            let path_elements: Vec<Fr> =
+                proof["pathElements"].as_array().unwrap().iter().map(field).collect();
+            assert_eq!(circuit.path.elements, path_elements);
+
+            let signals = synthesize(circuit).unwrap();
+            assert_eq!(signals[0], field(&note["nullifierHash"]));
+            assert_eq!(signals[1], field(&proof["leaf"]));
+            assert_eq!(signals[2], field(&proof["root"]));
+        }
+    }
+
+    #[test]
+    fn tampered_witnesses_are_rejected() {
+        let (notes, tree, _) = vectors();
+        let valid = spend(&tree, notes[0], 0);
+        let tamper = |change: fn(&mut EnhancedTransaction)| {
+            let mut circuit = valid.clone();
+            change(&mut circuit);
+            synthesize(circuit)
+        };
+
+        assert_eq!(tamper(|c| c.path.elements[3] += Fr::from(1u64)), None);
+        assert_eq!(tamper(|c| c.path.indices[0] = !c.path.indices[0]), None);
+        assert_eq!(tamper(|c| c.root += Fr::from(1u64)), None);
+        // Another note's secret changes the commitment, which is then not
+        // in the tree.
+        assert_eq!(tamper(|c| c.note.secret = Fr::from(12u64)), None);
+        assert_eq!(tamper(|c| c.max_amount = 99), None);
+        assert_eq!(tamper(|c| c.max_amount = 1 << 32), None);
+    }
+
+    #[test]
+    fn spend_checks_what_the_circuit_enforces() {
+        let (notes, tree, _) = vectors();
+        let spend = |note: Note, index, max_amount| {
+            EnhancedTransaction::spend(&tree, note, index, max_amount, Fr::zero())
+        };
+
+        assert_eq!(spend(notes[0], 1, MAX_AMOUNT), Err(WitnessError::NoteNotInTree));
+        assert_eq!(spend(notes[0], 0, 99), Err(WitnessError::OutOfRange));
+        assert_eq!(spend(notes[0], 0, 1 << 32), Err(WitnessError::OutOfRange));
+        assert!(spend(notes[0], 0, 100).is_ok());
+    }
+}
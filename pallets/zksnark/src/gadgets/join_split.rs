@@ -0,0 +1,265 @@
+//! `JoinSplit` from `circuits/join_split.circom`, synthesized from a
+//! [`JoinSplitWitness`].
+
+use super::{comparators::num2bits, merkle_tree::merkle_tree_checker, poseidon};
+use crate::{
+    join_split::{INPUTS, OUTPUTS},
+    utils::join_split::{JoinSplitWitness, MerklePath, Note},
+};
+use ark_bn254::Fr;
+use ark_ff::Zero;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    boolean::Boolean,
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+/// Bit width of every amount and the fee.
+const AMOUNT_BITS: usize = 64;
+
+impl JoinSplitWitness {
+    /// A witness over a tree of depth `levels` with every value zero, to set
+    /// keys up from.
+    pub fn blank(levels: usize) -> Self {
+        let zero = Note { asset: Fr::zero(), amount: 0, secret: Fr::zero(), nullifier: Fr::zero() };
+        let path = MerklePath { elements: vec![Fr::zero(); levels], indices: vec![false; levels] };
+        JoinSplitWitness {
+            root: Fr::zero(),
+            inputs: [zero; INPUTS],
+            paths: core::array::from_fn(|_| path.clone()),
+            outputs: [zero; OUTPUTS],
+            relayer: Fr::zero(),
+            fee_asset: Fr::zero(),
+            fee: 0,
+        }
+    }
+}
+
+/// A note's signals, allocated as witnesses.
+struct NoteVar {
+    asset: FpVar<Fr>,
+    amount: FpVar<Fr>,
+    secret: FpVar<Fr>,
+    nullifier: FpVar<Fr>,
+}
+
+impl NoteVar {
+    fn new(cs: &ConstraintSystemRef<Fr>, note: &Note) -> Result<Self, SynthesisError> {
+        let witness = |value: Fr| FpVar::new_witness(cs.clone(), || Ok(value));
+        Ok(NoteVar {
+            asset: witness(note.asset)?,
+            amount: witness(Fr::from(note.amount))?,
+            secret: witness(note.secret)?,
+            nullifier: witness(note.nullifier)?,
+        })
+    }
+
+    fn commitment(&self) -> Result<FpVar<Fr>, SynthesisError> {
+        poseidon::hash4(&self.asset, &self.amount, &self.secret, &self.nullifier)
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for JoinSplitWitness {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|note| NoteVar::new(&cs, note))
+            .collect::<Result<Vec<_>, _>>()?;
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|note| NoteVar::new(&cs, note))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Public signals, in the order circom exposes them.
+        let input = |value: Fr| FpVar::new_input(cs.clone(), || Ok(value));
+        let nullifier_hashes = self
+            .inputs
+            .iter()
+            .map(|note| input(note.nullifier_hash()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitments = self
+            .outputs
+            .iter()
+            .map(|note| input(note.commitment()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let root = input(self.root)?;
+        let _relayer = input(self.relayer)?;
+        let fee_asset = input(self.fee_asset)?;
+        let fee = input(Fr::from(self.fee))?;
+
+        let spent = inputs.iter().zip(&self.paths).zip(&nullifier_hashes);
+        for ((note, path), nullifier_hash) in spent {
+            num2bits(&note.amount, AMOUNT_BITS)?;
+            poseidon::hash2(&note.nullifier, &note.secret)?.enforce_equal(nullifier_hash)?;
+
+            let elements = path
+                .elements
+                .iter()
+                .map(|element| FpVar::new_witness(cs.clone(), || Ok(*element)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let indices = path
+                .indices
+                .iter()
+                .map(|index| Boolean::new_witness(cs.clone(), || Ok(*index)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let tree_root = merkle_tree_checker(&note.commitment()?, &elements, &indices)?;
+
+            // `ForceEqualIfEnabled`: a zero-value input pads a spend of
+            // fewer notes and need not be in the tree.
+            (&root - &tree_root).mul_equals(&note.amount, &FpVar::zero())?;
+        }
+
+        for (i, nullifier_hash) in nullifier_hashes.iter().enumerate() {
+            for later in &nullifier_hashes[i + 1..] {
+                nullifier_hash.is_eq(later)?.enforce_equal(&Boolean::FALSE)?;
+            }
+        }
+
+        for (note, commitment) in outputs.iter().zip(&commitments) {
+            num2bits(&note.amount, AMOUNT_BITS)?;
+            note.commitment()?.enforce_equal(commitment)?;
+        }
+        num2bits(&fee, AMOUNT_BITS)?;
+
+        // Value conservation, once for every asset that appears: the inputs
+        // of that asset are worth its outputs, plus the fee if it is paid in
+        // it. Every term is below 2^64, so neither side can wrap the field.
+        let terms: Vec<(&FpVar<Fr>, &FpVar<Fr>, bool)> = inputs
+            .iter()
+            .map(|note| (&note.asset, &note.amount, true))
+            .chain(outputs.iter().map(|note| (&note.asset, &note.amount, false)))
+            .chain([(&fee_asset, &fee, false)])
+            .collect();
+        for (asset, _, _) in &terms {
+            let mut balance = FpVar::zero();
+            for (other, amount, spent) in &terms {
+                let share = FpVar::from(asset.is_eq(other)?) * *amount;
+                if *spent {
+                    balance += share;
+                } else {
+                    balance -= share;
+                }
+            }
+            balance.enforce_equal(&FpVar::zero())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        poseidon::{self, Poseidon},
+        utils::{
+            join_split::InputNote,
+            merkle::{MemoryStore, MerkleTree},
+        },
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const DEPTH: u32 = 20;
+    /// Asset IDs, already mapped to field elements.
+    const NATIVE: Fr = ark_ff::MontFp!("1");
+    const TOKEN: Fr = ark_ff::MontFp!("2");
+
+    fn note(asset: Fr, amount: u64, seed: u64) -> Note {
+        Note { asset, amount, secret: Fr::from(seed), nullifier: Fr::from(seed + 1_000) }
+    }
+
+    /// A witness spending notes worth 100 of the native token and 50 of
+    /// another into `outputs`, with `fee` of the native token.
+    fn witness(outputs: [Note; OUTPUTS], fee: u64) -> JoinSplitWitness {
+        let notes = [note(NATIVE, 100, 1), note(TOKEN, 50, 2)];
+        let mut tree = MerkleTree::<Poseidon, _>::new(DEPTH, MemoryStore::default()).unwrap();
+        for note in &notes {
+            tree.insert(poseidon::to_h256(note.commitment())).unwrap();
+        }
+        let inputs = [0, 1].map(|i| InputNote { note: notes[i], leaf_index: Some(i as u32) });
+        JoinSplitWitness::new(&tree, inputs, outputs, Fr::from(9u64), NATIVE, fee).unwrap()
+    }
+
+    fn valid() -> JoinSplitWitness {
+        witness([note(NATIVE, 90, 3), note(TOKEN, 50, 4)], 10)
+    }
+
+    /// Synthesize `witness` and return its public inputs if it is satisfied.
+    fn synthesize(witness: JoinSplitWitness) -> Option<Vec<Fr>> {
+        let cs = ConstraintSystem::new_ref();
+        witness.generate_constraints(cs.clone()).unwrap();
+        if !cs.is_satisfied().unwrap() {
+            return None;
+        }
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        Some(instance[1..].to_vec())
+    }
+
+    #[test]
+    fn balanced_witnesses_satisfy_the_circuit() {
+        for witness in [valid(), witness([note(NATIVE, 100, 3), note(TOKEN, 50, 4)], 0)] {
+            let public_signals = witness.public_signals();
+            assert_eq!(synthesize(witness), Some(public_signals));
+        }
+    }
+
+    #[test]
+    fn padding_inputs_need_no_path() {
+        let spent = note(NATIVE, 100, 1);
+        let mut tree = MerkleTree::<Poseidon, _>::new(DEPTH, MemoryStore::default()).unwrap();
+        tree.insert(poseidon::to_h256(spent.commitment())).unwrap();
+        let inputs = [
+            InputNote { note: spent, leaf_index: Some(0) },
+            InputNote { note: note(NATIVE, 0, 2), leaf_index: None },
+        ];
+        let outputs = [note(NATIVE, 100, 3), note(NATIVE, 0, 4)];
+        let padded = JoinSplitWitness::new(&tree, inputs, outputs, Fr::zero(), NATIVE, 0);
+        assert!(synthesize(padded.unwrap()).is_some());
+    }
+
+    #[test]
+    fn value_must_be_conserved_per_asset() {
+        let tamper = |change: fn(&mut JoinSplitWitness)| {
+            let mut witness = valid();
+            change(&mut witness);
+            synthesize(witness)
+        };
+
+        assert_eq!(tamper(|w| w.outputs[0].amount += 1), None);
+        assert_eq!(tamper(|w| w.fee += 1), None);
+        assert_eq!(tamper(|w| w.fee_asset = TOKEN), None);
+        // The totals still balance, but value moves between assets.
+        assert_eq!(
+            tamper(|w| {
+                w.outputs[0].amount += 10;
+                w.outputs[1].amount -= 10;
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn inputs_must_be_in_the_tree_and_distinct() {
+        let tamper = |change: fn(&mut JoinSplitWitness)| {
+            let mut witness = valid();
+            change(&mut witness);
+            synthesize(witness)
+        };
+
+        assert_eq!(tamper(|w| w.root += Fr::from(1u64)), None);
+        assert_eq!(tamper(|w| w.paths[1].elements[0] += Fr::from(1u64)), None);
+        assert_eq!(tamper(|w| w.inputs[0].secret = Fr::from(99u64)), None);
+        assert_eq!(
+            tamper(|w| {
+                w.inputs[1] = w.inputs[0];
+                w.paths[1] = w.paths[0].clone();
+                w.outputs[1].asset = NATIVE;
+                w.outputs[1].amount = 100;
+            }),
+            None
+        );
+    }
+}
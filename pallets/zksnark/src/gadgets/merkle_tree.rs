@@ -0,0 +1,35 @@
+//! `DualMux` and `MerkleTreeChecker` from `circuits/merkle_tree.circom`.
+
+use super::poseidon;
+use ark_bn254::Fr;
+use ark_r1cs_std::{boolean::Boolean, fields::fp::FpVar};
+use ark_relations::r1cs::SynthesisError;
+
+/// `DualMux`: `(a, b)` when `swap` is false and `(b, a)` when it is true.
+pub fn dual_mux(
+    a: &FpVar<Fr>,
+    b: &FpVar<Fr>,
+    swap: &Boolean<Fr>,
+) -> Result<(FpVar<Fr>, FpVar<Fr>), SynthesisError> {
+    Ok((swap.select(b, a)?, swap.select(a, b)?))
+}
+
+/// `MerkleTreeChecker(levels)`: the root reached by hashing `leaf` up
+/// `path_elements`. `path_indices` holds, for every level, whether the
+/// path node is the right child.
+pub fn merkle_tree_checker(
+    leaf: &FpVar<Fr>,
+    path_elements: &[FpVar<Fr>],
+    path_indices: &[Boolean<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    if path_elements.len() != path_indices.len() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+
+    let mut node = leaf.clone();
+    for (sibling, is_right) in path_elements.iter().zip(path_indices) {
+        let (left, right) = dual_mux(&node, sibling, is_right)?;
+        node = poseidon::hash2(&left, &right)?;
+    }
+    Ok(node)
+}
@@ -0,0 +1,32 @@
+//! The circom circuits rebuilt as arkworks R1CS gadgets, so proofs can be
+//! made with [`crate::prover`] instead of snarkjs.
+//!
+//! Each gadget follows the circom template of the same name and computes
+//! the same outputs, though not with the same constraints. The circuits'
+//! public inputs are allocated in the order circom exposes them, so a key
+//! set up here verifies the same statements as the circom one.
+//!
+//! Two things differ from the templates throughout:
+//!
+//! - Public inputs that only bind a proof to an account, such as the
+//!   recipient or relayer, are allocated but never constrained. Groth16
+//!   binds every public input into the proof, so they need no
+//!   `recipientSquare`-style constraint of their own.
+//! - Each gadget has a `blank` constructor with every value zero. Setup only
+//!   reads the shape of a circuit, so this is what keys are made from.
+//!
+//! Keys for the gadgets are set up with [`crate::prover::setup`]. Those
+//! `build.rs` sets up are for the circom constraints, so proofs for them
+//! are made with [`crate::circom`] instead.
+
+pub mod comparators;
+pub mod enhanced_transaction;
+pub mod join_split;
+pub mod merkle_tree;
+pub mod poseidon;
+pub mod shield;
+pub mod unshield;
+
+pub use enhanced_transaction::EnhancedTransaction;
+pub use shield::Shield;
+pub use unshield::Unshield;
@@ -0,0 +1,92 @@
+//! `Poseidon(2)` and `Poseidon(4)` over allocated field elements, with the
+//! constants of [`crate::poseidon`].
+
+use crate::poseidon::{constants, FULL_ROUNDS, PARTIAL_ROUNDS_T3, PARTIAL_ROUNDS_T5};
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::{fp::FpVar, FieldVar};
+use ark_relations::r1cs::SynthesisError;
+
+/// `Poseidon(2)`: hash two field elements.
+pub fn hash2(a: &FpVar<Fr>, b: &FpVar<Fr>) -> Result<FpVar<Fr>, SynthesisError> {
+    permute(
+        [FpVar::zero(), a.clone(), b.clone()],
+        PARTIAL_ROUNDS_T3,
+        &constants::C3,
+        &constants::M3,
+    )
+}
+
+/// `Poseidon(4)`: hash four field elements.
+pub fn hash4(
+    a: &FpVar<Fr>,
+    b: &FpVar<Fr>,
+    c: &FpVar<Fr>,
+    d: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    permute(
+        [FpVar::zero(), a.clone(), b.clone(), c.clone(), d.clone()],
+        PARTIAL_ROUNDS_T5,
+        &constants::C5,
+        &constants::M5,
+    )
+}
+
+/// The permutation of [`crate::poseidon`], one S-box costing three
+/// constraints. Round constants and the MDS mix are linear and free.
+fn permute<const T: usize>(
+    mut state: [FpVar<Fr>; T],
+    partial_rounds: usize,
+    round_constants: &[Fr],
+    mds: &[[Fr; T]; T],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let rounds = FULL_ROUNDS + partial_rounds;
+    debug_assert_eq!(round_constants.len(), rounds * T);
+
+    for round in 0..rounds {
+        for (element, constant) in state.iter_mut().zip(&round_constants[round * T..]) {
+            *element += *constant;
+        }
+
+        let full = round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + partial_rounds;
+        if full {
+            for element in state.iter_mut() {
+                *element = sbox(element)?;
+            }
+        } else {
+            state[0] = sbox(&state[0])?;
+        }
+
+        state = core::array::from_fn(|i| {
+            mds[i].iter().zip(&state).map(|(m, element)| element * *m).sum()
+        });
+    }
+
+    Ok(state[0].clone())
+}
+
+fn sbox(x: &FpVar<Fr>) -> Result<FpVar<Fr>, SynthesisError> {
+    let x4 = x.square()?.square()?;
+    Ok(x4 * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon;
+    use ark_r1cs_std::{alloc::AllocVar, R1CSVar};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn matches_the_native_hash() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let values = [1u64, 2, 3, 4].map(Fr::from);
+        let [a, b, c, d] = values.map(|v| FpVar::new_witness(cs.clone(), || Ok(v)).unwrap());
+
+        assert_eq!(hash2(&a, &b).unwrap().value().unwrap(), poseidon::hash2(values[0], values[1]));
+        assert_eq!(
+            hash4(&a, &b, &c, &d).unwrap().value().unwrap(),
+            poseidon::hash4(values[0], values[1], values[2], values[3])
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
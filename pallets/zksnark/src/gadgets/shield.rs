@@ -0,0 +1,102 @@
+//! `Shield` from `circuits/shield.circom`.
+
+use super::{comparators::num2bits, poseidon};
+use crate::utils::join_split::Note;
+use ark_bn254::Fr;
+use ark_ff::Zero;
+use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+/// Bit width of `amount`.
+const AMOUNT_BITS: usize = 64;
+
+/// Every signal of `Shield()` that the prover supplies: the note being
+/// deposited, whose asset and amount are public.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shield {
+    pub note: Note,
+}
+
+impl Shield {
+    /// A circuit with every value zero, to set keys up from.
+    pub fn blank() -> Self {
+        let zero = Note { asset: Fr::zero(), amount: 0, secret: Fr::zero(), nullifier: Fr::zero() };
+        Shield { note: zero }
+    }
+
+    /// The circuit's public signals, in the order the verifier takes them.
+    pub fn public_signals(&self) -> Vec<Fr> {
+        vec![self.note.commitment(), self.note.asset, Fr::from(self.note.amount)]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for Shield {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let secret = FpVar::new_witness(cs.clone(), || Ok(self.note.secret))?;
+        let nullifier = FpVar::new_witness(cs.clone(), || Ok(self.note.nullifier))?;
+
+        // Public signals, in the order circom exposes them.
+        let input = |value: Fr| FpVar::new_input(cs.clone(), || Ok(value));
+        let commitment = input(self.note.commitment())?;
+        let asset = input(self.note.asset)?;
+        let amount = input(Fr::from(self.note.amount))?;
+
+        num2bits(&amount, AMOUNT_BITS)?;
+        poseidon::hash4(&asset, &amount, &secret, &nullifier)?.enforce_equal(&commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use core::str::FromStr;
+    use serde_json::Value;
+
+    /// The vectors `MerkleTreeChecker` is checked against, whose leaves are
+    /// the commitments of their notes.
+    const VECTORS: &str = include_str!("../circuits/test/merkle_tree_vectors.json");
+
+    fn field(value: &Value) -> Fr {
+        let decimal = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        Fr::from_str(&decimal).expect("vector holds a decimal field element")
+    }
+
+    /// Synthesize `circuit` and return its public inputs if it is satisfied.
+    fn synthesize(circuit: Shield) -> Option<Vec<Fr>> {
+        let cs = ConstraintSystem::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        if !cs.is_satisfied().unwrap() {
+            return None;
+        }
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        Some(instance[1..].to_vec())
+    }
+
+    #[test]
+    fn commitments_match_the_circom_vectors() {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let notes = vectors["notes"].as_array().unwrap();
+        for (note, proof) in notes.iter().zip(vectors["proofs"].as_array().unwrap()) {
+            let circuit = Shield {
+                note: Note {
+                    asset: field(&note["asset"]),
+                    amount: note["amount"].as_u64().unwrap(),
+                    secret: field(&note["secret"]),
+                    nullifier: field(&note["nullifier"]),
+                },
+            };
+            let public_signals = circuit.public_signals();
+            assert_eq!(public_signals[0], field(&proof["leaf"]));
+            assert_eq!(synthesize(circuit), Some(public_signals));
+        }
+    }
+
+    #[test]
+    fn blank_circuit_is_satisfied() {
+        assert!(synthesize(Shield::blank()).is_some());
+    }
+}
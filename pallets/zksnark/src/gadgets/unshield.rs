@@ -0,0 +1,216 @@
+//! `Unshield` from `circuits/unshield.circom`.
+
+use super::{
+    comparators::{less_eq_than, num2bits},
+    merkle_tree::merkle_tree_checker,
+    poseidon,
+};
+use crate::{
+    poseidon::Poseidon,
+    utils::{
+        join_split::{MerklePath, Note, WitnessError},
+        merkle::{MerkleTree, NodeStore},
+    },
+};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, eq::EqGadget, fields::fp::FpVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+/// Bit width of `amount` and `fee`.
+const AMOUNT_BITS: usize = 64;
+
+/// Every signal of `Unshield(levels)` that the prover supplies.
+///
+/// The note's asset and amount are public, so the pallet can pay them out
+/// of the pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unshield {
+    pub note: Note,
+    pub path: MerklePath,
+    pub root: Fr,
+    /// The recipient and relayer, as [`crate::transfer::account_input`]
+    /// maps them.
+    pub recipient: Fr,
+    pub relayer: Fr,
+    pub fee: u64,
+}
+
+impl Unshield {
+    /// A circuit over a tree of depth `levels` with every value zero, to set
+    /// keys up from.
+    pub fn blank(levels: usize) -> Self {
+        let zero = Note { asset: Fr::zero(), amount: 0, secret: Fr::zero(), nullifier: Fr::zero() };
+        Unshield {
+            note: zero,
+            path: MerklePath { elements: vec![Fr::zero(); levels], indices: vec![false; levels] },
+            root: Fr::zero(),
+            recipient: Fr::zero(),
+            relayer: Fr::zero(),
+            fee: 0,
+        }
+    }
+
+    /// Withdraw `note`, stored at `leaf_index` of `tree`, to `recipient`
+    /// with `fee` of it for `relayer`. Checks the ranges the circuit
+    /// enforces, so a witness that builds also proves.
+    ///
+    /// The store must keep every node so that the path can be read from it.
+    pub fn withdraw<S: NodeStore>(
+        tree: &MerkleTree<Poseidon, S>,
+        note: Note,
+        leaf_index: u32,
+        recipient: Fr,
+        relayer: Fr,
+        fee: u64,
+    ) -> Result<Self, WitnessError> {
+        if fee > note.amount {
+            return Err(WitnessError::OutOfRange);
+        }
+
+        Ok(Unshield {
+            note,
+            path: MerklePath::of_note(tree, &note, leaf_index)?,
+            root: Fr::from_be_bytes_mod_order(tree.root().as_bytes()),
+            recipient,
+            relayer,
+            fee,
+        })
+    }
+
+    /// The circuit's public signals, in the order the verifier takes them.
+    pub fn public_signals(&self) -> Vec<Fr> {
+        vec![
+            self.note.nullifier_hash(),
+            self.root,
+            self.note.asset,
+            Fr::from(self.note.amount),
+            self.recipient,
+            self.relayer,
+            Fr::from(self.fee),
+        ]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for Unshield {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let witness = |value: Fr| FpVar::new_witness(cs.clone(), || Ok(value));
+        let secret = witness(self.note.secret)?;
+        let nullifier = witness(self.note.nullifier)?;
+        let path_elements = self
+            .path
+            .elements
+            .iter()
+            .map(|element| witness(*element))
+            .collect::<Result<Vec<_>, _>>()?;
+        let path_indices = self
+            .path
+            .indices
+            .iter()
+            .map(|index| Boolean::new_witness(cs.clone(), || Ok(*index)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Public signals, in the order circom exposes them.
+        let input = |value: Fr| FpVar::new_input(cs.clone(), || Ok(value));
+        let nullifier_hash = input(self.note.nullifier_hash())?;
+        let root = input(self.root)?;
+        let asset = input(self.note.asset)?;
+        let amount = input(Fr::from(self.note.amount))?;
+        let _recipient = input(self.recipient)?;
+        let _relayer = input(self.relayer)?;
+        let fee = input(Fr::from(self.fee))?;
+
+        num2bits(&amount, AMOUNT_BITS)?;
+        num2bits(&fee, AMOUNT_BITS)?;
+        less_eq_than(&fee, &amount, AMOUNT_BITS)?.enforce_equal(&Boolean::TRUE)?;
+
+        let commitment = poseidon::hash4(&asset, &amount, &secret, &nullifier)?;
+        merkle_tree_checker(&commitment, &path_elements, &path_indices)?.enforce_equal(&root)?;
+
+        poseidon::hash2(&nullifier, &secret)?.enforce_equal(&nullifier_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::merkle::MemoryStore;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const DEPTH: u32 = 20;
+    const FEE: u64 = 10;
+
+    fn note(amount: u64, seed: u64) -> Note {
+        let (secret, nullifier) = (Fr::from(seed), Fr::from(seed + 1_000));
+        Note { asset: Fr::from(1u64), amount, secret, nullifier }
+    }
+
+    /// Two notes and the tree holding them.
+    fn tree() -> ([Note; 2], MerkleTree<Poseidon, MemoryStore>) {
+        let notes = [note(100, 1), note(50, 2)];
+        let mut tree = MerkleTree::new(DEPTH, MemoryStore::default()).unwrap();
+        for note in &notes {
+            tree.insert(crate::poseidon::to_h256(note.commitment())).unwrap();
+        }
+        (notes, tree)
+    }
+
+    fn withdraw(
+        tree: &MerkleTree<Poseidon, MemoryStore>,
+        note: Note,
+        index: u32,
+        fee: u64,
+    ) -> Result<Unshield, WitnessError> {
+        Unshield::withdraw(tree, note, index, Fr::from(2u64), Fr::from(3u64), fee)
+    }
+
+    /// Synthesize `circuit` and return its public inputs if it is satisfied.
+    fn synthesize(circuit: Unshield) -> Option<Vec<Fr>> {
+        let cs = ConstraintSystem::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        if !cs.is_satisfied().unwrap() {
+            return None;
+        }
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        Some(instance[1..].to_vec())
+    }
+
+    #[test]
+    fn witnesses_from_the_tree_satisfy_the_circuit() {
+        let (notes, tree) = tree();
+        for (index, note) in notes.into_iter().enumerate() {
+            for fee in [0, FEE, note.amount] {
+                let circuit = withdraw(&tree, note, index as u32, fee).unwrap();
+                let public_signals = circuit.public_signals();
+                assert_eq!(synthesize(circuit), Some(public_signals));
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_witnesses_are_rejected() {
+        let (notes, tree) = tree();
+        let valid = withdraw(&tree, notes[0], 0, FEE).unwrap();
+        let tamper = |change: fn(&mut Unshield)| {
+            let mut circuit = valid.clone();
+            change(&mut circuit);
+            synthesize(circuit)
+        };
+
+        assert_eq!(tamper(|c| c.path.elements[0] += Fr::from(1u64)), None);
+        assert_eq!(tamper(|c| c.path.indices[0] = !c.path.indices[0]), None);
+        assert_eq!(tamper(|c| c.root += Fr::from(1u64)), None);
+        // The asset and amount are part of the commitment, so changing
+        // either leaves a note that is not in the tree.
+        assert_eq!(tamper(|c| c.note.asset = Fr::from(2u64)), None);
+        assert_eq!(tamper(|c| c.note.amount += 1), None);
+        assert_eq!(tamper(|c| c.fee = c.note.amount + 1), None);
+    }
+
+    #[test]
+    fn withdraw_checks_what_the_circuit_enforces() {
+        let (notes, tree) = tree();
+        assert_eq!(withdraw(&tree, notes[0], 1, FEE), Err(WitnessError::NoteNotInTree));
+        assert_eq!(withdraw(&tree, notes[1], 1, 51), Err(WitnessError::OutOfRange));
+    }
+}
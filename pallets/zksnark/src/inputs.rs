@@ -0,0 +1,114 @@
+//! Public inputs handed to the Groth16 verifier.
+
+use crate::curve::{decode_canonical, field_size};
+use ark_ff::PrimeField;
+use frame_support::{traits::ConstU32, BoundedVec};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// Upper bound on the number of public inputs a single proof may carry.
+pub const MAX_PUBLIC_INPUTS: u32 = 64;
+
+/// Reasons an encoded list of public inputs can be rejected.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum PublicInputError {
+    /// The byte length is not a multiple of the field element size.
+    InvalidLength,
+    /// An element is not smaller than the scalar field modulus.
+    OutOfRange,
+    /// More than [`MAX_PUBLIC_INPUTS`] elements were supplied.
+    TooMany,
+}
+
+/// A bounded list of canonical scalar field elements.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PublicInputs<F: PrimeField>(BoundedVec<F, ConstU32<MAX_PUBLIC_INPUTS>>);
+
+impl<F: PrimeField> PublicInputs<F> {
+    /// Decode concatenated big-endian field elements. Every element must be
+    /// exactly `field_size::<F>()` bytes and strictly below the modulus, so
+    /// each list of inputs has a single byte encoding.
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<Self, PublicInputError> {
+        let size = field_size::<F>();
+        if bytes.len() % size != 0 {
+            return Err(PublicInputError::InvalidLength);
+        }
+        if bytes.len() / size > MAX_PUBLIC_INPUTS as usize {
+            return Err(PublicInputError::TooMany);
+        }
+
+        let elements = bytes
+            .chunks_exact(size)
+            .map(|chunk| decode_canonical::<F>(chunk).ok_or(PublicInputError::OutOfRange))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        elements
+            .try_into()
+            .map(Self)
+            .map_err(|_| PublicInputError::TooMany)
+    }
+
+    /// Number of public inputs.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no public inputs.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The decoded field elements, in order.
+    pub fn as_slice(&self) -> &[F] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::BigInteger;
+
+    fn be(element: Fr) -> Vec<u8> {
+        element.into_bigint().to_bytes_be()
+    }
+
+    #[test]
+    fn decodes_canonical_elements_in_order() {
+        let bytes = [be(Fr::from(7u64)), be(-Fr::from(1u64))].concat();
+        let inputs = PublicInputs::<Fr>::from_be_bytes(&bytes).unwrap();
+        assert_eq!(inputs.as_slice(), &[Fr::from(7u64), -Fr::from(1u64)]);
+        assert!(PublicInputs::<Fr>::from_be_bytes(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_inputs_not_below_the_modulus() {
+        let modulus = Fr::MODULUS.to_bytes_be();
+        assert_eq!(
+            PublicInputs::<Fr>::from_be_bytes(&[be(Fr::from(7u64)), modulus].concat()),
+            Err(PublicInputError::OutOfRange)
+        );
+        assert_eq!(
+            PublicInputs::<Fr>::from_be_bytes(&[0xff; 32]),
+            Err(PublicInputError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn rejects_lengths_that_are_not_whole_elements() {
+        let bytes = be(Fr::from(7u64));
+        assert_eq!(
+            PublicInputs::<Fr>::from_be_bytes(&bytes[1..]),
+            Err(PublicInputError::InvalidLength)
+        );
+        assert_eq!(
+            PublicInputs::<Fr>::from_be_bytes(&[bytes.as_slice(), &[0]].concat()),
+            Err(PublicInputError::InvalidLength)
+        );
+        assert_eq!(
+            PublicInputs::<Fr>::from_be_bytes(&bytes.repeat(MAX_PUBLIC_INPUTS as usize + 1)),
+            Err(PublicInputError::TooMany)
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! The statement a join-split proof is checked against.
+//!
+//! `JoinSplit(levels, 2, 2)` spends two notes and creates two, and exposes
+//! its public signals in this order:
+//!
+//! ```text
+//! inputNullifierHash[0], inputNullifierHash[1],
+//! outputCommitment[0], outputCommitment[1], root, relayer, feeAsset, fee
+//! ```
+//!
+//! The witness for it is built by [`crate::utils::join_split`].
+
+use crate::{
+    transfer::{account_input, asset_input, u64_input},
+    Proof,
+};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::H256;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// Notes spent by one join-split.
+pub const INPUTS: usize = 2;
+/// Notes created by one join-split.
+pub const OUTPUTS: usize = 2;
+
+/// One join-split, as submitted to `join_split`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct JoinSplit<AccountId, AssetId> {
+    pub proof: Proof,
+    pub root: H256,
+    /// Nullifier hashes of the spent notes.
+    pub nullifier_hashes: [H256; INPUTS],
+    /// Commitments to the created notes.
+    pub commitments: [H256; OUTPUTS],
+    /// Account allowed to collect `fee` for submitting the proof.
+    pub relayer: AccountId,
+    /// Asset the fee is paid in.
+    pub fee_asset: AssetId,
+    /// The part of the inputs' value not carried over to the outputs.
+    pub fee: u64,
+}
+
+impl<AccountId: Encode, AssetId: Encode> JoinSplit<AccountId, AssetId> {
+    /// Big-endian public inputs for the join-split circuit.
+    pub fn public_inputs(&self) -> Vec<u8> {
+        let mut inputs = Vec::with_capacity((INPUTS + OUTPUTS + 4) * 32);
+        for nullifier_hash in &self.nullifier_hashes {
+            inputs.extend_from_slice(nullifier_hash.as_bytes());
+        }
+        for commitment in &self.commitments {
+            inputs.extend_from_slice(commitment.as_bytes());
+        }
+        inputs.extend_from_slice(self.root.as_bytes());
+        inputs.extend_from_slice(&account_input(&self.relayer));
+        inputs.extend_from_slice(&asset_input(&self.fee_asset));
+        inputs.extend_from_slice(&u64_input(self.fee));
+        inputs
+    }
+}
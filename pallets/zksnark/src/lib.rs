@@ -1,207 +1,1227 @@
-use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine, Fq, Fq2};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
 use ark_ff::PrimeField;
-use ark_groth16::{prepare_verifying_key, Groth16, VerifyingKey};
+use ark_groth16::{prepare_verifying_key, Groth16, PreparedVerifyingKey, VerifyingKey};
 use codec::{Encode, Decode};
-use frame_support::pallet_prelude::*;
-use frame_system::{Config, Pallet};
+use frame_support::{
+    pallet_prelude::*,
+    traits::{
+        fungibles::{self, Inspect, Mutate},
+        tokens::Preservation,
+    },
+    PalletId,
+};
 use sp_std::vec::Vec;
-use sp_runtime::DispatchError;
-use core::marker::PhantomData;
+use sp_core::H256;
+use sp_runtime::{
+    traits::{AccountIdConversion, CheckedSub, Zero},
+    DispatchError,
+};
+use utils::merkle::MerkleTree;
 use ark_snark::SNARK;   
-use serde_json;
-use hex;
 
-#[derive(Clone, Encode, Decode, TypeInfo)]
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "std")]
+pub mod artifacts;
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod circom;
+pub mod curve;
+pub mod encoding;
+#[cfg(feature = "std")]
+pub mod gadgets;
+pub mod inputs;
+pub mod join_split;
+pub mod pool;
+pub mod poseidon;
+#[cfg(feature = "std")]
+pub mod prover;
+pub mod registry;
+pub mod shield;
+pub mod transfer;
+pub mod tree;
+pub mod utils;
+pub mod vk;
+pub mod weights;
+pub use curve::{Curve, Groth16Curve, PointError};
+pub use encoding::{ProofEncoding, ProofError};
+pub use inputs::{PublicInputError, PublicInputs};
+pub use join_split::JoinSplit;
+pub use pool::{PoolState, PoolStatus};
+pub use registry::{CircuitId, KeyStatus, VerifyingKeyInfo};
+pub use transfer::Transfer;
+pub use tree::{FrontierStore, TreeHasher};
+pub use vk::VerificationKeyError;
+pub use weights::WeightInfo;
+
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
 pub struct Proof {
+    pub circuit_id: CircuitId,
     pub a: Vec<u8>,
     pub b: Vec<u8>,
     pub c: Vec<u8>,
-    pub public_inputs: Vec<u8>,
+    pub encoding: ProofEncoding,
+}
+
+/// An asset notes can hold.
+pub type AssetIdOf<T> =
+    <<T as Config>::Assets as fungibles::Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+
+/// Balance of the assets notes can hold.
+pub type BalanceOf<T> =
+    <<T as Config>::Assets as fungibles::Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// Identifies a fixed-denomination pool by its asset and denomination.
+pub type PoolKeyOf<T> = (AssetIdOf<T>, BalanceOf<T>);
+
+/// The first proof in a batch that could not be verified, and why.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct BatchFailure {
+    /// Position of the proof in the batch.
+    pub index: u32,
+    pub error: DispatchError,
 }
 
-#[derive(Debug, PartialEq)] 
-pub enum Error<T> {
-    InvalidProof,
-    ProofVerificationFailed,
-    _Phantom(PhantomData<T>),
+/// What the benchmarks need from the runtime but cannot make generically.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AssetId> {
+    /// An asset that any account can be given by minting it.
+    fn asset() -> AssetId;
 }
 
-impl<T> From<Error<T>> for DispatchError {
-    fn from(_error: Error<T>) -> Self {
-        DispatchError::Other("ZkSnark verification error")
+/// Benchmarks with the native token.
+#[cfg(feature = "runtime-benchmarks")]
+impl<AssetId> BenchmarkHelper<frame_support::traits::fungible::NativeOrWithId<AssetId>> for () {
+    fn asset() -> frame_support::traits::fungible::NativeOrWithId<AssetId> {
+        frame_support::traits::fungible::NativeOrWithId::Native
+    }
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_system::pallet_prelude::*;
+    use sp_core::H256;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// The overarching runtime event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// The assets notes can hold, usually the native token alongside
+        /// issued assets.
+        type Assets: fungibles::Mutate<Self::AccountId>;
+
+        /// Account shielded funds are held in. Each fixed-denomination pool
+        /// holds its funds in a sub-account of it.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Maximum size in bytes of a serialized prepared verifying key.
+        #[pallet::constant]
+        type MaxVerifyingKeyLength: Get<u32>;
+
+        /// Depth of the commitment tree. The tree holds `2^depth` leaves and
+        /// must match the depth the transfer circuit was compiled for.
+        #[pallet::constant]
+        type MerkleTreeDepth: Get<u32>;
+
+        /// Number of recent tree roots proofs may be made against.
+        #[pallet::constant]
+        type RootHistorySize: Get<u32>;
+
+        /// Hash for the interior nodes of the commitment tree.
+        type TreeHasher: TreeHasher;
+
+        /// The `maxAmount` public input every transfer proof is checked
+        /// against. The circuit bounds amounts to 32 bits.
+        #[pallet::constant]
+        type MaxTransferAmount: Get<u64>;
+
+        /// The circuit `private_transfer` and `batch_private_transfer` proofs
+        /// must be made for.
+        #[pallet::constant]
+        type TransferCircuitId: Get<CircuitId>;
+
+        /// The circuit `shield` proofs must be made for.
+        #[pallet::constant]
+        type ShieldCircuitId: Get<CircuitId>;
+
+        /// The circuit `unshield` and `pool_withdraw` proofs must be made
+        /// for.
+        #[pallet::constant]
+        type UnshieldCircuitId: Get<CircuitId>;
+
+        /// The circuit `join_split` proofs must be made for.
+        #[pallet::constant]
+        type JoinSplitCircuitId: Get<CircuitId>;
+
+        /// Maximum number of transfers in a `batch_private_transfer` call.
+        #[pallet::constant]
+        type MaxBatchSize: Get<u32>;
+
+        /// Origin allowed to register, deprecate and retire verifying keys.
+        type RegistryOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Origin allowed to create fixed-denomination pools and to open or
+        /// close them.
+        type PoolOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Weight information for the extrinsics in this pallet.
+        type WeightInfo: WeightInfo;
+
+        /// Names the asset the benchmarks move.
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: BenchmarkHelper<AssetIdOf<Self>>;
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            let depth = T::MerkleTreeDepth::get();
+            assert!(
+                depth > 0 && depth < utils::merkle::MAX_DEPTH,
+                "MerkleTreeDepth must be in 1..32 so leaf counts fit in NextLeafIndex",
+            );
+        }
+    }
+
+    /// Verifying keys by the circuit they belong to.
+    #[pallet::storage]
+    pub type VerifyingKeys<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        CircuitId,
+        VerifyingKeyInfo<T::MaxVerifyingKeyLength>,
+        OptionQuery,
+    >;
+
+    /// Commitments created by successful private transfers.
+    #[pallet::storage]
+    pub type Commitments<T: Config> = StorageMap<_, Blake2_128Concat, H256, ()>;
+
+    /// Index the next commitment will be stored at in the tree.
+    #[pallet::storage]
+    pub type NextLeafIndex<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// The last left child hashed at each level of the commitment tree, and
+    /// its root at level `MerkleTreeDepth`. Levels no leaf has reached yet
+    /// are absent.
+    #[pallet::storage]
+    pub type Frontier<T: Config> = StorageMap<_, Twox64Concat, u32, H256>;
+
+    /// Ring buffer of the most recent `RootHistorySize` tree roots.
+    #[pallet::storage]
+    pub type RootHistory<T: Config> = StorageMap<_, Twox64Concat, u32, H256>;
+
+    /// Slot of `RootHistory` the next root will be written to.
+    #[pallet::storage]
+    pub type NextRootSlot<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// The roots currently in `RootHistory`, for constant-time lookup.
+    #[pallet::storage]
+    pub type KnownRoots<T: Config> = StorageMap<_, Identity, H256, ()>;
+
+    /// Nullifier hashes that have already been spent.
+    #[pallet::storage]
+    pub type NullifierSet<T: Config> = StorageMap<_, Blake2_128Concat, H256, ()>;
+
+    /// Fixed-denomination pools and their commitment trees.
+    #[pallet::storage]
+    pub type Pools<T: Config> =
+        StorageMap<_, Blake2_128Concat, PoolKeyOf<T>, PoolState<T::RootHistorySize>>;
+
+    /// Nullifier hashes that have already been spent from each pool.
+    #[pallet::storage]
+    pub type PoolNullifiers<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, PoolKeyOf<T>, Blake2_128Concat, H256, ()>;
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        /// snarkjs verification keys to register at version 1.
+        pub verifying_keys: Vec<(CircuitId, Vec<u8>)>,
+        #[serde(skip)]
+        pub _config: core::marker::PhantomData<T>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            // Shielded funds are transferred into the pool account. It may
+            // hold no native balance of its own, so keep it alive for assets
+            // that are not sufficient. The provider also keeps it alive when
+            // withdrawals empty it, so the last notes of an asset can be
+            // withdrawn in full.
+            let pool = Pallet::<T>::account_id();
+            if frame_system::Pallet::<T>::providers(&pool).is_zero() {
+                frame_system::Pallet::<T>::inc_providers(&pool);
+            }
+
+            for (circuit_id, key) in &self.verifying_keys {
+                Pallet::<T>::do_register_verifying_key(*circuit_id, key.clone())
+                    .expect("genesis verifying keys must be valid snarkjs keys");
+            }
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A private transfer was verified and its commitment appended to
+        /// the tree at `leaf_index`.
+        PrivateTransfer {
+            who: T::AccountId,
+            nullifier_hash: H256,
+            commitment: H256,
+            leaf_index: u32,
+            recipient: T::AccountId,
+        },
+        /// A join-split spent two notes and appended the commitments to the
+        /// two new ones to the tree at `leaf_indices`.
+        JoinSplit {
+            who: T::AccountId,
+            nullifier_hashes: [H256; join_split::INPUTS],
+            commitments: [H256; join_split::OUTPUTS],
+            leaf_indices: [u32; join_split::OUTPUTS],
+            relayer: T::AccountId,
+            fee_asset: AssetIdOf<T>,
+            fee: u64,
+        },
+        /// `amount` of `asset` was moved from `who` into the pool as a note
+        /// whose commitment was appended to the tree at `leaf_index`.
+        Shielded {
+            who: T::AccountId,
+            asset: AssetIdOf<T>,
+            amount: BalanceOf<T>,
+            commitment: H256,
+            leaf_index: u32,
+        },
+        /// A note worth `amount` of `asset` was spent out of the pool,
+        /// paying `amount - fee` to `recipient` and `fee` to `relayer`.
+        Unshielded {
+            nullifier_hash: H256,
+            asset: AssetIdOf<T>,
+            amount: BalanceOf<T>,
+            recipient: T::AccountId,
+            relayer: T::AccountId,
+            fee: BalanceOf<T>,
+        },
+        /// A fixed-denomination pool was created.
+        PoolCreated { asset: AssetIdOf<T>, denomination: BalanceOf<T> },
+        /// A pool was opened or closed to deposits.
+        PoolStatusChanged {
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+            status: PoolStatus,
+        },
+        /// `denomination` of `asset` was moved from `who` into the pool and
+        /// `commitment` appended to the pool's tree at `leaf_index`.
+        PoolDeposit {
+            who: T::AccountId,
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+            commitment: H256,
+            leaf_index: u32,
+        },
+        /// A note was spent out of the pool, paying `denomination - fee` to
+        /// `recipient` and `fee` to `relayer`.
+        PoolWithdrawal {
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+            nullifier_hash: H256,
+            recipient: T::AccountId,
+            relayer: T::AccountId,
+            fee: BalanceOf<T>,
+        },
+        /// A verifying key was registered or rotated.
+        VerifyingKeyRegistered { circuit_id: CircuitId, version: u32, curve: Curve },
+        /// A verifying key was marked as deprecated.
+        VerifyingKeyDeprecated { circuit_id: CircuitId, version: u32 },
+        /// A verifying key was retired and no longer accepts proofs.
+        VerifyingKeyRetired { circuit_id: CircuitId, version: u32 },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// `a` or `c` has the wrong length, a non-canonical coordinate or a
+        /// bad compressed encoding.
+        MalformedG1,
+        /// `b` has the wrong length, a non-canonical coordinate or a bad
+        /// compressed encoding.
+        MalformedG2,
+        /// A proof point does not satisfy the curve equation.
+        PointNotOnCurve,
+        /// A proof point lies outside the prime-order subgroup.
+        PointNotInSubgroup,
+        /// A snarkjs `proof.json` is malformed, or `b` and `c` are set
+        /// alongside it.
+        MalformedProofJson,
+        /// The proof is for a different curve than the verifying key.
+        ProofCurveMismatch,
+        /// The pairing check rejected the proof.
+        VerificationFailed,
+        /// The nullifier hash has already been spent.
+        NullifierAlreadyUsed,
+        /// The proof's Merkle root is not one of the recent tree roots.
+        UnknownRoot,
+        /// The commitment tree has no free leaves left.
+        MerkleTreeFull,
+        /// The public inputs are not a whole number of field elements.
+        MalformedPublicInputs,
+        /// A public input is not smaller than the scalar field modulus.
+        PublicInputOutOfRange,
+        /// The number of public inputs does not match the verification key.
+        WrongPublicInputCount,
+        /// No verifying key is registered for the circuit.
+        UnknownVerifyingKey,
+        /// The circuit's verifying key has been retired.
+        VerifyingKeyRetired,
+        /// The verifying key is not in a state that allows this transition.
+        InvalidKeyStatus,
+        /// The supplied verifying key could not be parsed.
+        InvalidVerifyingKey,
+        /// The supplied verifying key exceeds `MaxVerifyingKeyLength`.
+        VerifyingKeyTooLarge,
+        /// A batch call was made with no transfers.
+        EmptyBatch,
+        /// The proofs in a batch are not all for the same circuit.
+        MixedBatchCircuits,
+        /// The proof is not for the circuit the call expects.
+        WrongCircuit,
+        /// The amount does not fit in a note.
+        AmountTooLarge,
+        /// The relayer fee is larger than the amount withdrawn.
+        FeeExceedsAmount,
+        /// A pool already exists for the asset and denomination.
+        PoolExists,
+        /// No pool exists for the asset and denomination.
+        UnknownPool,
+        /// The pool is closed to deposits.
+        PoolClosed,
+        /// The denomination is below the asset's minimum balance.
+        DenominationBelowMinimum,
+        /// The commitment is not a field element, so no note opens it.
+        NonCanonicalCommitment,
+    }
+
+    impl<T> From<ProofError> for Error<T> {
+        fn from(error: ProofError) -> Self {
+            let point = |error: PointError, malformed: Self| match error {
+                PointError::NotOnCurve => Error::PointNotOnCurve,
+                PointError::NotInSubgroup => Error::PointNotInSubgroup,
+                PointError::InvalidLength
+                | PointError::NonCanonicalCoordinate
+                | PointError::InvalidEncoding => malformed,
+            };
+            match error {
+                ProofError::G1(error) => point(error, Error::MalformedG1),
+                ProofError::G2(error) => point(error, Error::MalformedG2),
+                ProofError::InvalidJson | ProofError::UnexpectedData => Error::MalformedProofJson,
+                ProofError::CurveMismatch => Error::ProofCurveMismatch,
+            }
+        }
+    }
+
+    impl<T> From<PublicInputError> for Error<T> {
+        fn from(error: PublicInputError) -> Self {
+            match error {
+                PublicInputError::InvalidLength => Error::MalformedPublicInputs,
+                PublicInputError::OutOfRange => Error::PublicInputOutOfRange,
+                PublicInputError::TooMany => Error::WrongPublicInputCount,
+            }
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Spend a note by proving knowledge of its opening against `root`,
+        /// recording `nullifier_hash` and the new `commitment`.
+        ///
+        /// `recipient` is a public input of the proof, so a copied proof
+        /// cannot be resubmitted for another account.
+        ///
+        /// No funds move. The transfer circuit commits the spent note again
+        /// as `commitment`, so `recipient` only names who the proof was made
+        /// out to. For the same reason the circuit has no relayer or fee
+        /// inputs: they would bind a payment this call never makes. Moving
+        /// value between notes and paying a relayer is what `join_split` is
+        /// for.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::private_transfer())]
+        pub fn private_transfer(
+            origin: OriginFor<T>,
+            proof: Proof,
+            nullifier_hash: H256,
+            commitment: H256,
+            root: H256,
+            recipient: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let transfer = Transfer { proof, nullifier_hash, commitment, root, recipient };
+
+            Self::check_transfer(&transfer, &[])?;
+            let verified = Self::verify_proof(
+                &transfer.proof,
+                &transfer.public_inputs(T::MaxTransferAmount::get()),
+            )?;
+            ensure!(verified, Error::<T>::VerificationFailed);
+
+            Self::apply_transfer(&who, transfer)
+        }
+
+        /// Register the snarkjs verification key for `circuit_id`. If the
+        /// circuit already has a key it is replaced and its version bumped.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::WeightInfo::register_verifying_key(key.len() as u32))]
+        pub fn register_verifying_key(
+            origin: OriginFor<T>,
+            circuit_id: CircuitId,
+            key: Vec<u8>,
+        ) -> DispatchResult {
+            T::RegistryOrigin::ensure_origin(origin)?;
+            Self::do_register_verifying_key(circuit_id, key)
+        }
+
+        /// Mark the key for `circuit_id` as deprecated. Proofs are still
+        /// accepted until the key is retired.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::deprecate_verifying_key())]
+        pub fn deprecate_verifying_key(origin: OriginFor<T>, circuit_id: CircuitId) -> DispatchResult {
+            T::RegistryOrigin::ensure_origin(origin)?;
+
+            let version = VerifyingKeys::<T>::try_mutate(circuit_id, |info| {
+                let info = info.as_mut().ok_or(Error::<T>::UnknownVerifyingKey)?;
+                ensure!(info.status == KeyStatus::Active, Error::<T>::InvalidKeyStatus);
+                info.status = KeyStatus::Deprecated;
+                Ok::<_, Error<T>>(info.version)
+            })?;
+
+            Self::deposit_event(Event::VerifyingKeyDeprecated { circuit_id, version });
+            Ok(())
+        }
+
+        /// Retire the key for `circuit_id`. Proofs against it are rejected
+        /// until a new key is registered for the circuit.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::retire_verifying_key())]
+        pub fn retire_verifying_key(origin: OriginFor<T>, circuit_id: CircuitId) -> DispatchResult {
+            T::RegistryOrigin::ensure_origin(origin)?;
+
+            let version = VerifyingKeys::<T>::try_mutate(circuit_id, |info| {
+                let info = info.as_mut().ok_or(Error::<T>::UnknownVerifyingKey)?;
+                ensure!(info.status != KeyStatus::Retired, Error::<T>::InvalidKeyStatus);
+                info.status = KeyStatus::Retired;
+                Ok::<_, Error<T>>(info.version)
+            })?;
+
+            Self::deposit_event(Event::VerifyingKeyRetired { circuit_id, version });
+            Ok(())
+        }
+
+        /// Apply several private transfers for the same circuit, verifying
+        /// all their proofs with a single batched pairing check. Either every
+        /// transfer is applied or none is.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::batch_private_transfer(transfers.len() as u32))]
+        pub fn batch_private_transfer(
+            origin: OriginFor<T>,
+            transfers: BoundedVec<Transfer<T::AccountId>, T::MaxBatchSize>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(!transfers.is_empty(), Error::<T>::EmptyBatch);
+
+            for (i, transfer) in transfers.iter().enumerate() {
+                Self::check_transfer(transfer, &transfers[..i])?;
+            }
+
+            let max_amount = T::MaxTransferAmount::get();
+            let batch: Vec<_> = transfers
+                .iter()
+                .map(|t| (&t.proof, t.public_inputs(max_amount)))
+                .collect();
+            Self::verify_batch(&batch).map_err(|failure| failure.error)?;
+
+            for transfer in transfers {
+                Self::apply_transfer(&who, transfer)?;
+            }
+            Ok(())
+        }
+
+        /// Spend two notes into two new ones, proving that for every asset
+        /// the inputs are worth exactly the outputs, plus `fee` for
+        /// `fee_asset`. Both nullifiers are recorded and both commitments
+        /// appended to the tree, or neither is.
+        ///
+        /// The fee leaves the shielded notes, so it is paid to `relayer` out
+        /// of the pool account. `relayer` and `fee` are public inputs of the
+        /// proof, so a copied proof cannot be resubmitted to collect it.
+        ///
+        /// A single note is spent by padding with a zero-value note, which
+        /// still needs a fresh nullifier.
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::join_split())]
+        #[allow(clippy::too_many_arguments)]
+        pub fn join_split(
+            origin: OriginFor<T>,
+            proof: Proof,
+            root: H256,
+            nullifier_hashes: [H256; join_split::INPUTS],
+            commitments: [H256; join_split::OUTPUTS],
+            relayer: T::AccountId,
+            fee_asset: AssetIdOf<T>,
+            fee: u64,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(proof.circuit_id == T::JoinSplitCircuitId::get(), Error::<T>::WrongCircuit);
+
+            for (i, nullifier_hash) in nullifier_hashes.iter().enumerate() {
+                ensure!(
+                    !NullifierSet::<T>::contains_key(nullifier_hash)
+                        && !nullifier_hashes[..i].contains(nullifier_hash),
+                    Error::<T>::NullifierAlreadyUsed
+                );
+            }
+            ensure!(KnownRoots::<T>::contains_key(root), Error::<T>::UnknownRoot);
+            let payout: BalanceOf<T> = fee.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+
+            let statement =
+                JoinSplit { proof, root, nullifier_hashes, commitments, relayer, fee_asset, fee };
+            let verified = Self::verify_proof(&statement.proof, &statement.public_inputs())?;
+            ensure!(verified, Error::<T>::VerificationFailed);
+
+            for nullifier_hash in nullifier_hashes {
+                NullifierSet::<T>::insert(nullifier_hash, ());
+            }
+            let mut leaf_indices = [0; join_split::OUTPUTS];
+            for (leaf_index, commitment) in leaf_indices.iter_mut().zip(commitments) {
+                Commitments::<T>::insert(commitment, ());
+                *leaf_index = Self::insert_leaf(commitment)?;
+            }
+            if !payout.is_zero() {
+                T::Assets::transfer(
+                    statement.fee_asset.clone(),
+                    &Self::account_id(),
+                    &statement.relayer,
+                    payout,
+                    Preservation::Expendable,
+                )?;
+            }
+
+            Self::deposit_event(Event::JoinSplit {
+                who,
+                nullifier_hashes,
+                commitments,
+                leaf_indices,
+                relayer: statement.relayer,
+                fee_asset: statement.fee_asset,
+                fee,
+            });
+            Ok(())
+        }
+
+        /// Move `amount` of `asset` from the signer into the pool account and
+        /// append `commitment` to the tree. `proof` shows that the note
+        /// commits to `asset` and `amount`, so it can never be spent for more
+        /// than was deposited.
+        ///
+        /// Until the pool account holds some of `asset`, deposits below the
+        /// asset's minimum balance are rejected.
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::shield())]
+        pub fn shield(
+            origin: OriginFor<T>,
+            asset: AssetIdOf<T>,
+            amount: BalanceOf<T>,
+            commitment: H256,
+            proof: Proof,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(proof.circuit_id == T::ShieldCircuitId::get(), Error::<T>::WrongCircuit);
+
+            let note_amount: u64 = amount.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+            let inputs = shield::shield_inputs(&commitment, &asset, note_amount);
+            let verified = Self::verify_proof(&proof, &inputs)?;
+            ensure!(verified, Error::<T>::VerificationFailed);
+
+            let pool = Self::account_id();
+            T::Assets::transfer(asset.clone(), &who, &pool, amount, Preservation::Expendable)?;
+            Commitments::<T>::insert(commitment, ());
+            let leaf_index = Self::insert_leaf(commitment)?;
+
+            Self::deposit_event(Event::Shielded { who, asset, amount, commitment, leaf_index });
+            Ok(())
+        }
+
+        /// Spend a note worth `amount` of `asset` out of the pool, paying
+        /// `amount - fee` to `recipient` and `fee` to `relayer`.
+        ///
+        /// `recipient`, `relayer` and `fee` are public inputs of the proof,
+        /// so anyone may submit it, and the recipient needs no balance to
+        /// withdraw.
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::unshield())]
+        #[allow(clippy::too_many_arguments)]
+        pub fn unshield(
+            origin: OriginFor<T>,
+            proof: Proof,
+            nullifier_hash: H256,
+            root: H256,
+            asset: AssetIdOf<T>,
+            amount: BalanceOf<T>,
+            recipient: T::AccountId,
+            relayer: T::AccountId,
+            fee: BalanceOf<T>,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            ensure!(proof.circuit_id == T::UnshieldCircuitId::get(), Error::<T>::WrongCircuit);
+            ensure!(
+                !NullifierSet::<T>::contains_key(nullifier_hash),
+                Error::<T>::NullifierAlreadyUsed
+            );
+            ensure!(KnownRoots::<T>::contains_key(root), Error::<T>::UnknownRoot);
+            let payout = amount.checked_sub(&fee).ok_or(Error::<T>::FeeExceedsAmount)?;
+
+            let note_amount: u64 = amount.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+            let note_fee: u64 = fee.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+            let inputs = shield::unshield_inputs(
+                &nullifier_hash,
+                &root,
+                &asset,
+                note_amount,
+                &recipient,
+                &relayer,
+                note_fee,
+            );
+            let verified = Self::verify_proof(&proof, &inputs)?;
+            ensure!(verified, Error::<T>::VerificationFailed);
+
+            NullifierSet::<T>::insert(nullifier_hash, ());
+            let pool = Self::account_id();
+            for (to, value) in [(&recipient, payout), (&relayer, fee)] {
+                if !value.is_zero() {
+                    let preservation = Preservation::Expendable;
+                    T::Assets::transfer(asset.clone(), &pool, to, value, preservation)?;
+                }
+            }
+
+            Self::deposit_event(Event::Unshielded {
+                nullifier_hash,
+                asset,
+                amount,
+                recipient,
+                relayer,
+                fee,
+            });
+            Ok(())
+        }
+
+        /// Create a pool whose notes are each worth `denomination` of
+        /// `asset`.
+        ///
+        /// A pool is never removed: once closed its notes can still be
+        /// withdrawn, and its nullifiers must be kept for as long as they
+        /// can.
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::create_pool())]
+        pub fn create_pool(
+            origin: OriginFor<T>,
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+        ) -> DispatchResult {
+            T::PoolOrigin::ensure_origin(origin)?;
+            let key = (asset.clone(), denomination);
+            ensure!(!Pools::<T>::contains_key(&key), Error::<T>::PoolExists);
+            // A pool's account is emptied by its last withdrawal, so every
+            // deposit must be able to recreate it.
+            let minimum = T::Assets::minimum_balance(asset.clone());
+            ensure!(
+                !denomination.is_zero() && denomination >= minimum,
+                Error::<T>::DenominationBelowMinimum
+            );
+            let _: u64 = denomination.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+
+            // The pool account never holds a native balance of its own, so
+            // keep it alive for assets that are not sufficient. The provider
+            // is never released, since the pool is never removed.
+            frame_system::Pallet::<T>::inc_providers(&Self::pool_account_id(&key));
+            Pools::<T>::insert(&key, PoolState::default());
+
+            Self::deposit_event(Event::PoolCreated { asset, denomination });
+            Ok(())
+        }
+
+        /// Open or close a pool to deposits. Withdrawals are always allowed.
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::set_pool_status())]
+        pub fn set_pool_status(
+            origin: OriginFor<T>,
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+            status: PoolStatus,
+        ) -> DispatchResult {
+            T::PoolOrigin::ensure_origin(origin)?;
+            Pools::<T>::try_mutate((asset.clone(), denomination), |pool| {
+                let pool = pool.as_mut().ok_or(Error::<T>::UnknownPool)?;
+                pool.status = status;
+                Ok::<_, Error<T>>(())
+            })?;
+
+            Self::deposit_event(Event::PoolStatusChanged { asset, denomination, status });
+            Ok(())
+        }
+
+        /// Move `denomination` of `asset` from the signer into the pool and
+        /// append `commitment` to the pool's tree.
+        ///
+        /// No proof is needed: a note that does not commit to the pool's
+        /// denomination can never be withdrawn. A commitment that is not a
+        /// field element is refused, as no note could ever open it.
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::pool_deposit())]
+        pub fn pool_deposit(
+            origin: OriginFor<T>,
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+            commitment: H256,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                poseidon::from_h256(&commitment).is_some(),
+                Error::<T>::NonCanonicalCommitment
+            );
+            let key = (asset.clone(), denomination);
+            let pool = Pools::<T>::get(&key).ok_or(Error::<T>::UnknownPool)?;
+            ensure!(pool.status == PoolStatus::Open, Error::<T>::PoolClosed);
+
+            T::Assets::transfer(
+                asset.clone(),
+                &who,
+                &Self::pool_account_id(&key),
+                denomination,
+                Preservation::Expendable,
+            )?;
+            let leaf_index = Self::insert_pool_leaf(&key, pool, commitment)?;
+
+            Self::deposit_event(Event::PoolDeposit {
+                who,
+                asset,
+                denomination,
+                commitment,
+                leaf_index,
+            });
+            Ok(())
+        }
+
+        /// Spend a note out of a pool, paying `denomination - fee` to
+        /// `recipient` and `fee` to `relayer`.
+        ///
+        /// `proof` is an `unshield` proof for `amount = denomination` against
+        /// one of the pool's recent roots.
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::pool_withdraw())]
+        #[allow(clippy::too_many_arguments)]
+        pub fn pool_withdraw(
+            origin: OriginFor<T>,
+            asset: AssetIdOf<T>,
+            denomination: BalanceOf<T>,
+            proof: Proof,
+            nullifier_hash: H256,
+            root: H256,
+            recipient: T::AccountId,
+            relayer: T::AccountId,
+            fee: BalanceOf<T>,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            ensure!(proof.circuit_id == T::UnshieldCircuitId::get(), Error::<T>::WrongCircuit);
+            let key = (asset.clone(), denomination);
+            let pool = Pools::<T>::get(&key).ok_or(Error::<T>::UnknownPool)?;
+            ensure!(
+                !PoolNullifiers::<T>::contains_key(&key, nullifier_hash),
+                Error::<T>::NullifierAlreadyUsed
+            );
+            ensure!(pool.is_known_root(&root), Error::<T>::UnknownRoot);
+            let payout = denomination.checked_sub(&fee).ok_or(Error::<T>::FeeExceedsAmount)?;
+
+            let note_amount: u64 =
+                denomination.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+            let note_fee: u64 = fee.try_into().map_err(|_| Error::<T>::AmountTooLarge)?;
+            let inputs = shield::unshield_inputs(
+                &nullifier_hash,
+                &root,
+                &asset,
+                note_amount,
+                &recipient,
+                &relayer,
+                note_fee,
+            );
+            let verified = Self::verify_proof(&proof, &inputs)?;
+            ensure!(verified, Error::<T>::VerificationFailed);
+
+            PoolNullifiers::<T>::insert(&key, nullifier_hash, ());
+            let account = Self::pool_account_id(&key);
+            for (to, value) in [(&recipient, payout), (&relayer, fee)] {
+                if !value.is_zero() {
+                    let preservation = Preservation::Expendable;
+                    T::Assets::transfer(asset.clone(), &account, to, value, preservation)?;
+                }
+            }
+
+            Self::deposit_event(Event::PoolWithdrawal {
+                asset,
+                denomination,
+                nullifier_hash,
+                recipient,
+                relayer,
+                fee,
+            });
+            Ok(())
+        }
     }
 }
 
 pub trait ZkSnarkVerifier {
-    fn verify_proof(
-        proof: &Proof,
-        root: &[u8],
-        nullifier_hash: &[u8],
-        commitment: &[u8],
-    ) -> Result<bool, DispatchError>;
+    /// Verify `proof` against the registered key for its circuit, with
+    /// concatenated big-endian public inputs.
+    fn verify_proof(proof: &Proof, public_inputs: &[u8]) -> Result<bool, DispatchError>;
 
-    fn decode_g1_point(bytes: &[u8]) -> Result<G1Affine, &'static str>;
+    fn decode_g1_point<E: Groth16Curve>(bytes: &[u8]) -> Result<E::G1Affine, PointError>;
     
-    fn decode_g2_point(bytes: &[u8]) -> Result<G2Affine, &'static str>;
+    fn decode_g2_point<E: Groth16Curve>(bytes: &[u8]) -> Result<E::G2Affine, PointError>;
     
-    fn parse_verification_key(vk_bytes: &[u8]) -> Result<VerifyingKey<Bls12_381>, &'static str>;
+    fn decode_proof<E: Groth16Curve>(proof: &Proof) -> Result<ark_groth16::Proof<E>, ProofError>;
+    
+    fn parse_verification_key<E: Groth16Curve>(vk_bytes: &[u8]) -> Result<VerifyingKey<E>, VerificationKeyError>;
     
     fn verify_groth16(
-        pi_a: &[u8],
-        pi_b: &[u8],
-        pi_c: &[u8],
+        proof: &Proof,
         public_inputs: &[u8],
         vk_bytes: &[u8]
     ) -> Result<bool, DispatchError>;
+
+    /// Verify proofs for a single circuit together, each paired with its
+    /// big-endian public inputs. On failure, reports the first bad proof.
+    fn verify_batch(batch: &[(&Proof, Vec<u8>)]) -> Result<(), BatchFailure>;
 }
 
 impl<T: Config> ZkSnarkVerifier for Pallet<T> {
-    fn verify_proof(
-        proof: &Proof,
-        root: &[u8],
-        nullifier_hash: &[u8],
-        commitment: &[u8],
-    ) -> Result<bool, DispatchError> {
-        let vk_bytes = include_bytes!(
-            concat!(env!("OUT_DIR"), "/zksnark/verification_key.json")
-        );
-        
-        let mut public_inputs = Vec::new();
-        public_inputs.extend_from_slice(root);
-        public_inputs.extend_from_slice(nullifier_hash);
-        public_inputs.extend_from_slice(commitment);
+    fn verify_proof(proof: &Proof, public_inputs: &[u8]) -> Result<bool, DispatchError> {
+        let info = VerifyingKeys::<T>::get(proof.circuit_id)
+            .ok_or(Error::<T>::UnknownVerifyingKey)?;
+        ensure!(info.status.accepts_proofs(), Error::<T>::VerifyingKeyRetired);
 
-        Self::verify_groth16(
-            &proof.a,
-            &proof.b,
-            &proof.c,
-            &public_inputs,
-            vk_bytes
-        )
+        match info.curve {
+            Curve::Bn254 => Self::verify_registered::<Bn254>(proof, public_inputs, &info.key),
+            Curve::Bls12_381 => Self::verify_registered::<Bls12_381>(proof, public_inputs, &info.key),
+        }
     }
 
     fn verify_groth16(
-        pi_a: &[u8],
-        pi_b: &[u8],
-        pi_c: &[u8],
+        proof: &Proof,
         public_inputs: &[u8],
         vk_bytes: &[u8]
     ) -> Result<bool, DispatchError> {
-        let a_points = Self::decode_g1_point(pi_a)
-            .map_err(|_| Error::<T>::InvalidProof)?;
-        let b_points = Self::decode_g2_point(pi_b)
-            .map_err(|_| Error::<T>::InvalidProof)?;
-        let c_points = Self::decode_g1_point(pi_c)
-            .map_err(|_| Error::<T>::InvalidProof)?;
-    
-        let vk = Self::parse_verification_key(vk_bytes)
-            .map_err(|_| Error::<T>::InvalidProof)?;
-    
-        let pvk = prepare_verifying_key(&vk);
-    
-        let mut inputs = Vec::new();
-        for chunk in public_inputs.chunks(32) {
-            let mut bytes = [0u8; 32];
-            bytes.copy_from_slice(chunk);
-            let fr = Fr::from_be_bytes_mod_order(&bytes);
-            inputs.push(fr);
+        let curve = Curve::of_snarkjs_key(vk_bytes)
+            .map_err(|_| Error::<T>::InvalidVerifyingKey)?;
+
+        match curve {
+            Curve::Bn254 => Self::verify_groth16_on::<Bn254>(proof, public_inputs, vk_bytes),
+            Curve::Bls12_381 => Self::verify_groth16_on::<Bls12_381>(proof, public_inputs, vk_bytes),
         }
-    
-        let proof = ark_groth16::Proof {
-            a: a_points,
-            b: b_points,
-            c: c_points,
+    }
+
+    fn verify_batch(batch: &[(&Proof, Vec<u8>)]) -> Result<(), BatchFailure> {
+        let Some((first, _)) = batch.first() else {
+            return Ok(());
         };
-    
-        let verified = Groth16::<Bls12_381>::verify_with_processed_vk(
-            &pvk, 
-            &inputs, 
+        let circuit_id = first.circuit_id;
+        let fail = |index: usize, error: DispatchError| BatchFailure { index: index as u32, error };
+
+        if let Some(index) = batch.iter().position(|(proof, _)| proof.circuit_id != circuit_id) {
+            return Err(fail(index, Error::<T>::MixedBatchCircuits.into()));
+        }
+        let info = VerifyingKeys::<T>::get(circuit_id)
+            .ok_or_else(|| fail(0, Error::<T>::UnknownVerifyingKey.into()))?;
+        if !info.status.accepts_proofs() {
+            return Err(fail(0, Error::<T>::VerifyingKeyRetired.into()));
+        }
+
+        match info.curve {
+            Curve::Bn254 => Self::verify_batch_on::<Bn254>(batch, &info.key),
+            Curve::Bls12_381 => Self::verify_batch_on::<Bls12_381>(batch, &info.key),
+        }
+    }
+
+    fn decode_g1_point<E: Groth16Curve>(bytes: &[u8]) -> Result<E::G1Affine, PointError> {
+        E::decode_g1(bytes)
+    }
+
+    fn decode_g2_point<E: Groth16Curve>(bytes: &[u8]) -> Result<E::G2Affine, PointError> {
+        E::decode_g2(bytes)
+    }
+
+    fn decode_proof<E: Groth16Curve>(proof: &Proof) -> Result<ark_groth16::Proof<E>, ProofError> {
+        E::decode_proof(proof)
+    }
+
+    fn parse_verification_key<E: Groth16Curve>(vk_bytes: &[u8]) -> Result<VerifyingKey<E>, VerificationKeyError> {
+        E::parse_verification_key(vk_bytes)
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// The account shielded funds are held in.
+    pub fn account_id() -> T::AccountId {
+        T::PalletId::get().into_account_truncating()
+    }
+
+    /// The account a fixed-denomination pool's funds are held in. It is
+    /// derived from a hash of the pool's key: the key itself may be longer
+    /// than a sub-account seed, and truncating it could give two pools one
+    /// account.
+    pub fn pool_account_id(key: &PoolKeyOf<T>) -> T::AccountId {
+        let seed = sp_io::hashing::blake2_256(&key.encode());
+        T::PalletId::get().into_sub_account_truncating(seed)
+    }
+
+    /// Checks on `transfer` that do not need its proof: the proof is for the
+    /// transfer circuit, the nullifier is unspent, including by `earlier`
+    /// transfers of the same batch, and the root is recent.
+    fn check_transfer(
+        transfer: &Transfer<T::AccountId>,
+        earlier: &[Transfer<T::AccountId>],
+    ) -> DispatchResult {
+        ensure!(
+            transfer.proof.circuit_id == T::TransferCircuitId::get(),
+            Error::<T>::WrongCircuit
+        );
+        ensure!(
+            !NullifierSet::<T>::contains_key(transfer.nullifier_hash)
+                && !earlier.iter().any(|t| t.nullifier_hash == transfer.nullifier_hash),
+            Error::<T>::NullifierAlreadyUsed
+        );
+        ensure!(KnownRoots::<T>::contains_key(transfer.root), Error::<T>::UnknownRoot);
+        Ok(())
+    }
+
+    /// Record a verified transfer: spend its nullifier and append its
+    /// commitment to the tree.
+    fn apply_transfer(who: &T::AccountId, transfer: Transfer<T::AccountId>) -> DispatchResult {
+        let Transfer { nullifier_hash, commitment, recipient, .. } = transfer;
+
+        NullifierSet::<T>::insert(nullifier_hash, ());
+        Commitments::<T>::insert(commitment, ());
+        let leaf_index = Self::insert_leaf(commitment)?;
+
+        Self::deposit_event(Event::PrivateTransfer {
+            who: who.clone(),
+            nullifier_hash,
+            commitment,
+            leaf_index,
+            recipient,
+        });
+        Ok(())
+    }
+
+    /// The commitment tree over its frontier in storage.
+    fn commitment_tree() -> Result<MerkleTree<T::TreeHasher, FrontierStore<T>>, DispatchError> {
+        MerkleTree::new(T::MerkleTreeDepth::get(), FrontierStore::<T>::default())
+            .map_err(|_| Error::<T>::MerkleTreeFull.into())
+    }
+
+    /// Current root of the commitment tree. Until the first insert it is the
+    /// root of `MerkleTreeDepth` levels of empty subtrees.
+    pub fn current_root() -> Result<H256, DispatchError> {
+        Ok(Self::commitment_tree()?.root())
+    }
+
+    /// Append `leaf` to the commitment tree and record the new root,
+    /// returning the leaf's index. Only calls that take in a note may do this,
+    /// so it is not part of the pallet's public interface.
+    pub(crate) fn insert_leaf(leaf: H256) -> Result<u32, DispatchError> {
+        let mut tree = Self::commitment_tree()?;
+        let index = tree.insert(leaf).map_err(|_| Error::<T>::MerkleTreeFull)?;
+
+        Self::record_root(tree.root());
+        Ok(index)
+    }
+
+    /// Append `leaf` to a pool's tree and store the pool with its new root,
+    /// returning the leaf's index.
+    fn insert_pool_leaf(
+        key: &PoolKeyOf<T>,
+        pool: PoolState<T::RootHistorySize>,
+        leaf: H256,
+    ) -> Result<u32, DispatchError> {
+        let mut tree = MerkleTree::<T::TreeHasher, _>::new(T::MerkleTreeDepth::get(), pool)
+            .map_err(|_| Error::<T>::MerkleTreeFull)?;
+        let index = tree.insert(leaf).map_err(|_| Error::<T>::MerkleTreeFull)?;
+
+        let root = tree.root();
+        let mut pool = tree.into_store();
+        pool.record_root(root);
+        Pools::<T>::insert(key, pool);
+        Ok(index)
+    }
+
+    /// Push `root` into the history, forgetting the oldest root once the
+    /// ring buffer is full.
+    fn record_root(root: H256) {
+        let slot = NextRootSlot::<T>::get();
+        if let Some(evicted) = RootHistory::<T>::get(slot) {
+            KnownRoots::<T>::remove(evicted);
+        }
+        RootHistory::<T>::insert(slot, root);
+        KnownRoots::<T>::insert(root, ());
+        NextRootSlot::<T>::put((slot + 1) % T::RootHistorySize::get().max(1));
+    }
+
+    /// Validate `key` and store it as the next version of `circuit_id`'s key.
+    pub fn do_register_verifying_key(circuit_id: CircuitId, key: Vec<u8>) -> DispatchResult {
+        let curve = Curve::of_snarkjs_key(&key).map_err(|_| Error::<T>::InvalidVerifyingKey)?;
+        let prepared = match curve {
+            Curve::Bn254 => Self::parse_verification_key::<Bn254>(&key)
+                .map(|vk| vk::serialize_prepared(&vk)),
+            Curve::Bls12_381 => Self::parse_verification_key::<Bls12_381>(&key)
+                .map(|vk| vk::serialize_prepared(&vk)),
+        }
+        .map_err(|_| Error::<T>::InvalidVerifyingKey)?;
+
+        let key: BoundedVec<u8, T::MaxVerifyingKeyLength> =
+            prepared.try_into().map_err(|_| Error::<T>::VerifyingKeyTooLarge)?;
+        let version = VerifyingKeys::<T>::get(circuit_id)
+            .map_or(1, |previous| previous.version.saturating_add(1));
+
+        VerifyingKeys::<T>::insert(
+            circuit_id,
+            VerifyingKeyInfo { version, curve, status: KeyStatus::Active, key },
+        );
+
+        Self::deposit_event(Event::VerifyingKeyRegistered { circuit_id, version, curve });
+        Ok(())
+    }
+
+    /// Run the Groth16 check over the pairing engine `E` against a snarkjs
+    /// JSON key, preparing the key on the fly.
+    fn verify_groth16_on<E: Groth16Curve>(
+        proof: &Proof,
+        public_inputs: &[u8],
+        vk_bytes: &[u8]
+    ) -> Result<bool, DispatchError> {
+        let vk = Self::parse_verification_key::<E>(vk_bytes)
+            .map_err(|_| Error::<T>::InvalidVerifyingKey)?;
+
+        Self::verify_prepared::<E>(proof, public_inputs, &prepare_verifying_key(&vk))
+    }
+
+    /// Run the Groth16 check against a prepared key from the registry.
+    ///
+    /// The key is decoded again for every proof rather than cached. The
+    /// registry already holds it prepared, and decoding it unchecked takes
+    /// about 2% of the check. A cache would have to outlive the call, and
+    /// the runtime keeps nothing between calls but storage.
+    fn verify_registered<E: Groth16Curve>(
+        proof: &Proof,
+        public_inputs: &[u8],
+        prepared_vk: &[u8]
+    ) -> Result<bool, DispatchError> {
+        let pvk = vk::deserialize_prepared::<E>(prepared_vk)
+            .map_err(|_| Error::<T>::InvalidVerifyingKey)?;
+
+        Self::verify_prepared::<E>(proof, public_inputs, &pvk)
+    }
+
+    fn verify_prepared<E: Groth16Curve>(
+        proof: &Proof,
+        public_inputs: &[u8],
+        pvk: &PreparedVerifyingKey<E>
+    ) -> Result<bool, DispatchError> {
+        let (proof, inputs) = Self::decode_statement::<E>(proof, public_inputs, pvk)?;
+
+        let verified = Groth16::<E>::verify_with_processed_vk(
+            pvk,
+            &inputs,
             &proof
-        ).map_err(|_| Error::<T>::ProofVerificationFailed)?;
-        
+        ).map_err(|_| Error::<T>::VerificationFailed)?;
+
         Ok(verified)
     }
 
-    fn decode_g1_point(bytes: &[u8]) -> Result<G1Affine, &'static str> {
-        if bytes.len() != 64 {  
-            return Err("Invalid G1 point length");
-        }
-        
-        let mut x_bytes = [0u8; 32];
-        let mut y_bytes = [0u8; 32];
-        x_bytes.copy_from_slice(&bytes[..32]);
-        y_bytes.copy_from_slice(&bytes[32..]);
-        
-        let x = Fq::from_be_bytes_mod_order(&x_bytes);
-        let y = Fq::from_be_bytes_mod_order(&y_bytes);
-        
-        Ok(G1Affine::new(x, y))
-    }
-
-    fn decode_g2_point(bytes: &[u8]) -> Result<G2Affine, &'static str> {
-        if bytes.len() != 128 {
-            return Err("Invalid G2 point length");
-        }
-        
-        let mut x_c0_bytes = [0u8; 32];
-        let mut x_c1_bytes = [0u8; 32];
-        let mut y_c0_bytes = [0u8; 32];
-        let mut y_c1_bytes = [0u8; 32];
-        
-        x_c0_bytes.copy_from_slice(&bytes[..32]);
-        x_c1_bytes.copy_from_slice(&bytes[32..64]);
-        y_c0_bytes.copy_from_slice(&bytes[64..96]);
-        y_c1_bytes.copy_from_slice(&bytes[96..]);
-        
-        let x_c0 = Fq::from_be_bytes_mod_order(&x_c0_bytes);
-        let x_c1 = Fq::from_be_bytes_mod_order(&x_c1_bytes);
-        let y_c0 = Fq::from_be_bytes_mod_order(&y_c0_bytes);
-        let y_c1 = Fq::from_be_bytes_mod_order(&y_c1_bytes);
-        
-        let x = Fq2::new(x_c0, x_c1);
-        let y = Fq2::new(y_c0, y_c1);
-        
-        Ok(G2Affine::new(x, y))
-    }
-
-    fn parse_verification_key(vk_bytes: &[u8]) -> Result<VerifyingKey<Bls12_381>, &'static str> {
-        let vk: serde_json::Value = serde_json::from_slice(vk_bytes)
-            .map_err(|_| "Failed to parse verification key")?;
-        
-        let alpha = vk["alpha"]
-            .as_array()
-            .ok_or("Invalid alpha in vk")?;
-            
-        let beta = vk["beta"]
-            .as_array()
-            .ok_or("Invalid beta in vk")?;
-            
-        let gamma = vk["gamma"]
-            .as_array()
-            .ok_or("Invalid gamma in vk")?;
-            
-        let delta = vk["delta"]
-            .as_array()
-            .ok_or("Invalid delta in vk")?;
-
-        let ic = vk["IC"]
-            .as_array()
-            .ok_or("Invalid IC in vk")?;
-
-        let mut gamma_abc_g1 = Vec::new();
-        for point in ic {
-            let point_hex = point.as_str().ok_or("Invalid IC point")?;
-            let point_bytes = hex::decode(point_hex).map_err(|_| "Invalid IC point hex")?;
-            let g1_point = Self::decode_g1_point(&point_bytes)?;
-            gamma_abc_g1.push(g1_point);
-        }
-
-        Ok(VerifyingKey {
-            alpha_g1: Self::decode_g1_point(&hex::decode(alpha[0].as_str().ok_or("Invalid alpha hex")?).map_err(|_| "Invalid alpha encoding")?)?,
-            beta_g2: Self::decode_g2_point(&hex::decode(beta[0].as_str().ok_or("Invalid beta hex")?).map_err(|_| "Invalid beta encoding")?)?,
-            gamma_g2: Self::decode_g2_point(&hex::decode(gamma[0].as_str().ok_or("Invalid gamma hex")?).map_err(|_| "Invalid gamma encoding")?)?,
-            delta_g2: Self::decode_g2_point(&hex::decode(delta[0].as_str().ok_or("Invalid delta hex")?).map_err(|_| "Invalid delta encoding")?)?,
-            gamma_abc_g1,
-        })
+    /// Check a batch against a prepared key from the registry. If the
+    /// combined check fails, the proofs are re-checked one at a time to find
+    /// the one at fault.
+    fn verify_batch_on<E: Groth16Curve>(
+        batch: &[(&Proof, Vec<u8>)],
+        prepared_vk: &[u8]
+    ) -> Result<(), BatchFailure> {
+        let fail = |index: usize, error: DispatchError| BatchFailure { index: index as u32, error };
+
+        let pvk = vk::deserialize_prepared::<E>(prepared_vk)
+            .map_err(|_| fail(0, Error::<T>::InvalidVerifyingKey.into()))?;
+        let statements = batch
+            .iter()
+            .enumerate()
+            .map(|(index, (proof, inputs))| {
+                Self::decode_statement::<E>(proof, inputs, &pvk).map_err(|error| fail(index, error))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if crate::batch::verify(&pvk, &statements, &Self::batch_randomizers(batch)) {
+            return Ok(());
+        }
+
+        for (index, (proof, inputs)) in statements.iter().enumerate() {
+            let verified = Groth16::<E>::verify_with_processed_vk(&pvk, inputs, proof)
+                .unwrap_or(false);
+            if !verified {
+                return Err(fail(index, Error::<T>::VerificationFailed.into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a proof and its public inputs, checking the input count
+    /// against the key.
+    fn decode_statement<E: Groth16Curve>(
+        proof: &Proof,
+        public_inputs: &[u8],
+        pvk: &PreparedVerifyingKey<E>
+    ) -> Result<(ark_groth16::Proof<E>, Vec<E::ScalarField>), DispatchError> {
+        let proof = Self::decode_proof::<E>(proof).map_err(Error::<T>::from)?;
+
+        let inputs = PublicInputs::<E::ScalarField>::from_be_bytes(public_inputs)
+            .map_err(Error::<T>::from)?;
+        ensure!(
+            inputs.len() + 1 == pvk.vk.gamma_abc_g1.len(),
+            Error::<T>::WrongPublicInputCount
+        );
+
+        Ok((proof, inputs.as_slice().to_vec()))
+    }
+
+    /// 128-bit weights for [`batch::verify`], derived from the parent block
+    /// hash and the batch itself so a submitter cannot choose them.
+    fn batch_randomizers<F: PrimeField>(batch: &[(&Proof, Vec<u8>)]) -> Vec<F> {
+        let seed = sp_io::hashing::blake2_256(
+            &(frame_system::Pallet::<T>::parent_hash(), batch).encode(),
+        );
+        (0..batch.len() as u32)
+            .map(|i| {
+                let hash = sp_io::hashing::blake2_256(&(seed, i).encode());
+                F::from_le_bytes_mod_order(&hash[..16])
+            })
+            .collect()
     }
 }
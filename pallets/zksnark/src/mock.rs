@@ -0,0 +1,114 @@
+use crate::{self as pallet_zksnark, artifacts::Circuit};
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{
+        fungible::{NativeFromLeft, NativeOrWithId, UnionOf},
+        AsEnsureOriginWithArg,
+    },
+    PalletId,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        ZkSnark: pallet_zksnark,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig)]
+impl pallet_assets::Config for Test {
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<u64>>;
+    type ForceOrigin = EnsureRoot<u64>;
+    type Freezer = ();
+}
+
+/// The native currency alongside every issued asset.
+pub type NativeAndAssets = UnionOf<Balances, Assets, NativeFromLeft, NativeOrWithId<u32>, u64>;
+
+parameter_types! {
+    pub const ZkSnarkPalletId: PalletId = PalletId(*b"zk/shpol");
+    pub const MaxVerifyingKeyLength: u32 = 128 * 1024;
+    pub const MerkleTreeDepth: u32 = 20;
+    pub const RootHistorySize: u32 = 30;
+    pub const MaxTransferAmount: u64 = 1_000;
+    pub const TransferCircuitId: u32 = 0;
+    pub const ShieldCircuitId: u32 = 2;
+    pub const UnshieldCircuitId: u32 = 3;
+    pub const JoinSplitCircuitId: u32 = 1;
+    pub const MaxBatchSize: u32 = 4;
+}
+
+impl pallet_zksnark::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Assets = NativeAndAssets;
+    type PalletId = ZkSnarkPalletId;
+    type MaxVerifyingKeyLength = MaxVerifyingKeyLength;
+    type MerkleTreeDepth = MerkleTreeDepth;
+    type RootHistorySize = RootHistorySize;
+    type TreeHasher = pallet_zksnark::poseidon::Poseidon;
+    type MaxTransferAmount = MaxTransferAmount;
+    type TransferCircuitId = TransferCircuitId;
+    type ShieldCircuitId = ShieldCircuitId;
+    type UnshieldCircuitId = UnshieldCircuitId;
+    type JoinSplitCircuitId = JoinSplitCircuitId;
+    type MaxBatchSize = MaxBatchSize;
+    type RegistryOrigin = EnsureRoot<u64>;
+    type PoolOrigin = EnsureRoot<u64>;
+    type WeightInfo = ();
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+/// Balance every account from 1 to 4 starts with.
+pub const INITIAL_BALANCE: u64 = 1_000_000;
+
+/// The keys `build.rs` set up, under the circuit IDs above, as a
+/// development chain's genesis registers them.
+fn genesis_keys() -> Vec<(u32, Vec<u8>)> {
+    [
+        (TransferCircuitId::get(), Circuit::Transfer),
+        (JoinSplitCircuitId::get(), Circuit::JoinSplit),
+        (ShieldCircuitId::get(), Circuit::Shield),
+        (UnshieldCircuitId::get(), Circuit::Unshield),
+    ]
+    .into_iter()
+    .map(|(id, circuit)| (id, circuit.verification_key().to_vec()))
+    .collect()
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let storage = RuntimeGenesisConfig {
+        balances: pallet_balances::GenesisConfig {
+            balances: (1..=4).map(|who| (who, INITIAL_BALANCE)).collect(),
+            ..Default::default()
+        },
+        zk_snark: pallet_zksnark::GenesisConfig {
+            verifying_keys: genesis_keys(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+    .build_storage()
+    .unwrap();
+    let mut ext: sp_io::TestExternalities = storage.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,116 @@
+//! Fixed-denomination pools.
+//!
+//! Every deposit into a pool is worth exactly its denomination, so all of a
+//! pool's notes look alike and a withdrawal cannot be linked to a deposit
+//! by its amount. A pool is identified by the asset it holds and its
+//! denomination, and keeps its own commitment tree, root history and
+//! nullifier set apart from the main shielded pool.
+//!
+//! Notes are the ones `EnhancedTransaction` spends,
+//! `Poseidon(asset, denomination, secret, nullifier)`, and are withdrawn
+//! with an `Unshield` proof for the pool's asset and
+//! `amount = denomination` against one of the pool's roots.
+
+use crate::utils::merkle::{NodeStore, MAX_DEPTH};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    traits::{ConstU32, Get},
+    BoundedVec, CloneNoBound, DefaultNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+};
+use scale_info::TypeInfo;
+use sp_core::H256;
+use sp_runtime::RuntimeDebug;
+
+/// Whether a pool takes new deposits.
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo,
+)]
+pub enum PoolStatus {
+    /// Deposits and withdrawals are accepted.
+    #[default]
+    Open,
+    /// Deposits are rejected. Notes already in the pool can still be
+    /// withdrawn.
+    Closed,
+}
+
+/// A pool's status and commitment tree.
+///
+/// The tree is kept as a frontier, like the main pool's, but in a single
+/// storage item: a pool is read and written once per deposit.
+#[derive(
+    CloneNoBound,
+    DefaultNoBound,
+    PartialEqNoBound,
+    EqNoBound,
+    Encode,
+    Decode,
+    MaxEncodedLen,
+    RuntimeDebugNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(HistorySize))]
+#[codec(mel_bound())]
+pub struct PoolState<HistorySize: Get<u32>> {
+    pub status: PoolStatus,
+    /// Index the next commitment will be stored at.
+    pub next_leaf_index: u32,
+    /// The last left child hashed at each level, with the root at the top.
+    pub frontier: BoundedVec<H256, ConstU32<{ MAX_DEPTH + 1 }>>,
+    /// The most recent roots, oldest first until the buffer wraps.
+    pub roots: BoundedVec<H256, HistorySize>,
+    /// Slot of `roots` the next root will be written to once it is full.
+    pub next_root_slot: u32,
+}
+
+impl<HistorySize: Get<u32>> PoolState<HistorySize> {
+    /// Push `root` into the history, forgetting the oldest root once it
+    /// holds `HistorySize` roots.
+    pub fn record_root(&mut self, root: H256) {
+        if self.roots.try_push(root).is_ok() {
+            return;
+        }
+        if let Some(oldest) = self.roots.get_mut(self.next_root_slot as usize) {
+            *oldest = root;
+        }
+        self.next_root_slot = (self.next_root_slot + 1) % HistorySize::get().max(1);
+    }
+
+    /// Whether `root` is one of the recent roots of the pool's tree.
+    pub fn is_known_root(&self, root: &H256) -> bool {
+        self.roots.contains(root)
+    }
+}
+
+impl<HistorySize: Get<u32>> NodeStore for PoolState<HistorySize> {
+    fn leaf_count(&self) -> u64 {
+        self.next_leaf_index.into()
+    }
+
+    fn set_leaf_count(&mut self, count: u64) {
+        // `integrity_test` bounds the depth so the count fits.
+        self.next_leaf_index = count as u32;
+    }
+
+    fn node(&self, level: u32, index: u64) -> Option<H256> {
+        if index & 1 == 0 {
+            self.frontier.get(level as usize).copied()
+        } else {
+            None
+        }
+    }
+
+    fn set_node(&mut self, level: u32, index: u64, node: H256) {
+        if index & 1 != 0 {
+            return;
+        }
+        // Levels are written from the leaves up, so the first insert fills
+        // the frontier in order.
+        match self.frontier.get_mut(level as usize) {
+            Some(stored) => *stored = node,
+            None => {
+                let _ = self.frontier.try_push(node);
+            }
+        }
+    }
+}
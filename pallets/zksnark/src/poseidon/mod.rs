@@ -0,0 +1,217 @@
+//! Poseidon over the BN254 scalar field, bit-for-bit compatible with
+//! circomlib's `Poseidon(n)` template.
+//!
+//! Only the widths our circuits use are provided: `t = 3` for
+//! `Poseidon(2)` (Merkle nodes, nullifier hashes), `t = 4` for
+//! `Poseidon(3)` and `t = 5` for `Poseidon(4)` (note commitments). All use
+//! the `x^5` S-box, 8 full rounds and circomlib's partial round counts.
+
+pub(crate) mod constants;
+
+use crate::{
+    curve::decode_canonical,
+    tree::TreeHasher,
+    utils::merkle::{self, MAX_DEPTH},
+};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, Field, MontFp, PrimeField};
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+/// Number of full rounds, split evenly before and after the partial rounds.
+pub(crate) const FULL_ROUNDS: usize = 8;
+/// Partial rounds for `t = 3`.
+pub(crate) const PARTIAL_ROUNDS_T3: usize = 57;
+/// Partial rounds for `t = 4`.
+pub(crate) const PARTIAL_ROUNDS_T4: usize = 56;
+/// Partial rounds for `t = 5`.
+pub(crate) const PARTIAL_ROUNDS_T5: usize = 60;
+
+/// `Poseidon(2)`: hash two field elements.
+pub fn hash2(a: Fr, b: Fr) -> Fr {
+    permute(
+        [Fr::from(0u64), a, b],
+        PARTIAL_ROUNDS_T3,
+        &constants::C3,
+        &constants::M3,
+    )
+}
+
+/// `Poseidon(3)`: hash three field elements.
+pub fn hash3(a: Fr, b: Fr, c: Fr) -> Fr {
+    permute(
+        [Fr::from(0u64), a, b, c],
+        PARTIAL_ROUNDS_T4,
+        &constants::C4,
+        &constants::M4,
+    )
+}
+
+/// `Poseidon(4)`: hash four field elements.
+pub fn hash4(a: Fr, b: Fr, c: Fr, d: Fr) -> Fr {
+    permute(
+        [Fr::from(0u64), a, b, c, d],
+        PARTIAL_ROUNDS_T5,
+        &constants::C5,
+        &constants::M5,
+    )
+}
+
+/// Run the permutation on `state`, whose first element is the zero
+/// capacity element, and return the first element of the result.
+fn permute<const T: usize>(
+    mut state: [Fr; T],
+    partial_rounds: usize,
+    round_constants: &[Fr],
+    mds: &[[Fr; T]; T],
+) -> Fr {
+    let rounds = FULL_ROUNDS + partial_rounds;
+    debug_assert_eq!(round_constants.len(), rounds * T);
+
+    for round in 0..rounds {
+        for (element, constant) in state.iter_mut().zip(&round_constants[round * T..]) {
+            *element += constant;
+        }
+
+        let full = round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + partial_rounds;
+        if full {
+            state.iter_mut().for_each(sbox);
+        } else {
+            sbox(&mut state[0]);
+        }
+
+        let mut mixed = [Fr::from(0u64); T];
+        for (out, row) in mixed.iter_mut().zip(mds) {
+            *out = row.iter().zip(&state).map(|(m, s)| *m * s).sum();
+        }
+        state = mixed;
+    }
+
+    state[0]
+}
+
+fn sbox(x: &mut Fr) {
+    let x2 = x.square();
+    *x *= x2.square();
+}
+
+/// Big-endian encoding of a field element, as used for public inputs.
+pub fn to_h256(element: Fr) -> H256 {
+    let bytes = element.into_bigint().to_bytes_be();
+    H256::from_slice(&bytes)
+}
+
+/// Decode a big-endian field element, refusing values `>=` the modulus.
+pub fn from_h256(hash: &H256) -> Option<Fr> {
+    decode_canonical(hash.as_bytes())
+}
+
+/// Roots of empty subtrees of height `0..=MAX_DEPTH`: the zero leaf, then
+/// `Poseidon(2)` of the level below with itself.
+const ZERO_HASHES: [Fr; MAX_DEPTH as usize + 1] = [
+    MontFp!("0"),
+    MontFp!("14744269619966411208579211824598458697587494354926760081771325075741142829156"),
+    MontFp!("7423237065226347324353380772367382631490014989348495481811164164159255474657"),
+    MontFp!("11286972368698509976183087595462810875513684078608517520839298933882497716792"),
+    MontFp!("3607627140608796879659380071776844901612302623152076817094415224584923813162"),
+    MontFp!("19712377064642672829441595136074946683621277828620209496774504837737984048981"),
+    MontFp!("20775607673010627194014556968476266066927294572720319469184847051418138353016"),
+    MontFp!("3396914609616007258851405644437304192397291162432396347162513310381425243293"),
+    MontFp!("21551820661461729022865262380882070649935529853313286572328683688269863701601"),
+    MontFp!("6573136701248752079028194407151022595060682063033565181951145966236778420039"),
+    MontFp!("12413880268183407374852357075976609371175688755676981206018884971008854919922"),
+    MontFp!("14271763308400718165336499097156975241954733520325982997864342600795471836726"),
+    MontFp!("20066985985293572387227381049700832219069292839614107140851619262827735677018"),
+    MontFp!("9394776414966240069580838672673694685292165040808226440647796406499139370960"),
+    MontFp!("11331146992410411304059858900317123658895005918277453009197229807340014528524"),
+    MontFp!("15819538789928229930262697811477882737253464456578333862691129291651619515538"),
+    MontFp!("19217088683336594659449020493828377907203207941212636669271704950158751593251"),
+    MontFp!("21035245323335827719745544373081896983162834604456827698288649288827293579666"),
+    MontFp!("6939770416153240137322503476966641397417391950902474480970945462551409848591"),
+    MontFp!("10941962436777715901943463195175331263348098796018438960955633645115732864202"),
+    MontFp!("15019797232609675441998260052101280400536945603062888308240081994073687793470"),
+    MontFp!("11702828337982203149177882813338547876343922920234831094975924378932809409969"),
+    MontFp!("11217067736778784455593535811108456786943573747466706329920902520905755780395"),
+    MontFp!("16072238744996205792852194127671441602062027943016727953216607508365787157389"),
+    MontFp!("17681057402012993898104192736393849603097507831571622013521167331642182653248"),
+    MontFp!("21694045479371014653083846597424257852691458318143380497809004364947786214945"),
+    MontFp!("8163447297445169709687354538480474434591144168767135863541048304198280615192"),
+    MontFp!("14081762237856300239452543304351251708585712948734528663957353575674639038357"),
+    MontFp!("16619959921569409661790279042024627172199214148318086837362003702249041851090"),
+    MontFp!("7022159125197495734384997711896547675021391130223237843255817587255104160365"),
+    MontFp!("4114686047564160449611603615418567457008101555090703535405891656262658644463"),
+    MontFp!("12549363297364877722388257367377629555213421373705596078299904496781819142130"),
+    MontFp!("21443572485391568159800782191812935835534334817699172242223315142338162256601"),
+];
+
+/// [`TreeHasher`] matching the `Poseidon(2)` nodes of `MerkleTreeChecker`.
+pub struct Poseidon;
+
+impl TreeHasher for Poseidon {
+    /// Children are read as big-endian field elements; values that are not
+    /// canonical are reduced modulo the field order.
+    fn hash(left: &H256, right: &H256) -> H256 {
+        let left = Fr::from_be_bytes_mod_order(left.as_bytes());
+        let right = Fr::from_be_bytes_mod_order(right.as_bytes());
+        to_h256(hash2(left, right))
+    }
+
+    /// Read from a precomputed table, so opening the tree costs no hashing.
+    fn zero_hashes(depth: u32) -> Vec<H256> {
+        match ZERO_HASHES.get(..=depth as usize) {
+            Some(zeros) => zeros.iter().copied().map(to_h256).collect(),
+            None => merkle::zero_hashes::<Self>(depth),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    fn fr(decimal: &str) -> Fr {
+        Fr::from_str(decimal).unwrap()
+    }
+
+    // Outputs of circomlib's `Poseidon(2)`, `Poseidon(3)` and `Poseidon(4)`
+    // on the inputs `1, 2, 3, 4`, computed by the compiled templates.
+
+    #[test]
+    fn hash2_matches_circomlib() {
+        assert_eq!(
+            hash2(Fr::from(1u64), Fr::from(2u64)),
+            fr("7853200120776062878684798364095072458815029376092732009249414926327459813530")
+        );
+    }
+
+    #[test]
+    fn hash3_matches_circomlib() {
+        assert_eq!(
+            hash3(Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)),
+            fr("6542985608222806190361240322586112750744169038454362455181422643027100751666")
+        );
+    }
+
+    #[test]
+    fn hash4_matches_circomlib() {
+        assert_eq!(
+            hash4(Fr::from(1u64), Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)),
+            fr("18821383157269793795438455681495246036402687001665670618754263018637548127333")
+        );
+    }
+
+    #[test]
+    fn zero_hash_table_matches_the_hash() {
+        assert_eq!(Poseidon::zero_hashes(MAX_DEPTH), merkle::zero_hashes::<Poseidon>(MAX_DEPTH));
+        assert_eq!(Poseidon::zero_hashes(3), merkle::zero_hashes::<Poseidon>(3));
+        assert_eq!(Poseidon::zero_hashes(MAX_DEPTH + 1).len(), MAX_DEPTH as usize + 2);
+    }
+
+    #[test]
+    fn tree_nodes_are_hash2_of_their_children() {
+        let (left, right) = (Fr::from(1u64), Fr::from(2u64));
+        let node = Poseidon::hash(&to_h256(left), &to_h256(right));
+        assert_eq!(from_h256(&node), Some(hash2(left, right)));
+    }
+}
@@ -0,0 +1,238 @@
+//! Groth16 proving in Rust, for clients that would otherwise run snarkjs.
+//!
+//! [`setup`] generates keys for any arkworks circuit, such as the circom
+//! circuits rebuilt in [`crate::gadgets`], [`prove`] turns a
+//! witness into a [`Proof`] the pallet accepts, along with the public inputs
+//! it proves, and [`verify`] runs the check the pallet runs. A key is
+//! registered on chain by exporting it with
+//! [`Groth16Curve::export_verification_key`].
+//!
+//! The setup is circuit-specific and its toxic waste comes from the caller's
+//! RNG, so the keys are only fit for development and tests. Production keys
+//! come from a ceremony.
+
+use crate::{
+    curve::{field_size, Groth16Curve},
+    encoding::{ProofEncoding, ProofError},
+    inputs::{PublicInputError, PublicInputs},
+    registry::CircuitId,
+    Proof,
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{
+    r1cs_to_qap::{LibsnarkReduction, R1CSToQAP},
+    Groth16, ProvingKey, VerifyingKey,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+use ark_serialize::CanonicalSerialize;
+use ark_snark::SNARK;
+use ark_std::rand::{CryptoRng, RngCore};
+
+/// A proof and the public inputs it was made for.
+///
+/// Only `proof` is submitted: the pallet rebuilds the inputs from the
+/// call's arguments, so a client compares them with `public_inputs` to
+/// catch a mismatch before paying for a failed call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProvenStatement {
+    pub proof: Proof,
+    /// Big-endian field elements, in the form
+    /// [`ZkSnarkVerifier::verify_proof`](crate::ZkSnarkVerifier::verify_proof)
+    /// takes.
+    pub public_inputs: Vec<u8>,
+}
+
+/// Reasons a proof could not be made or checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProverError {
+    /// The circuit failed to synthesize, usually because a value is missing.
+    Synthesis(SynthesisError),
+    /// The witness does not satisfy the circuit, so no valid proof exists.
+    Unsatisfied,
+    /// The points of a [`Proof`] could not be decoded.
+    Proof(ProofError),
+    /// The public inputs a proof was checked against could not be decoded.
+    PublicInputs(PublicInputError),
+    /// The proving key was set up for a circuit of another shape.
+    KeyMismatch,
+}
+
+impl From<SynthesisError> for ProverError {
+    fn from(error: SynthesisError) -> Self {
+        ProverError::Synthesis(error)
+    }
+}
+
+/// Generate a proving and verifying key for the shape of `circuit`. Its
+/// values are not used and may be left unassigned.
+pub fn setup<E, C, R>(
+    circuit: C,
+    rng: &mut R,
+) -> Result<(ProvingKey<E>, VerifyingKey<E>), ProverError>
+where
+    E: Pairing,
+    C: ConstraintSynthesizer<E::ScalarField>,
+    R: RngCore + CryptoRng,
+{
+    Ok(Groth16::<E>::circuit_specific_setup(circuit, rng)?)
+}
+
+/// Prove `circuit` under `circuit_id`.
+///
+/// The witness is checked before proving, since Groth16 would otherwise
+/// produce a proof the verifier rejects. The proof's points are
+/// compressed.
+pub fn prove<E, C, R>(
+    pk: &ProvingKey<E>,
+    circuit: C,
+    circuit_id: CircuitId,
+    rng: &mut R,
+) -> Result<ProvenStatement, ProverError>
+where
+    E: Pairing,
+    C: ConstraintSynthesizer<E::ScalarField> + Clone,
+    R: RngCore + CryptoRng,
+{
+    prove_with_reduction::<E, LibsnarkReduction, _, _>(pk, circuit, circuit_id, rng)
+}
+
+/// [`prove`] with a key set up for the R1CS-to-QAP reduction `QAP`.
+pub(crate) fn prove_with_reduction<E, QAP, C, R>(
+    pk: &ProvingKey<E>,
+    circuit: C,
+    circuit_id: CircuitId,
+    rng: &mut R,
+) -> Result<ProvenStatement, ProverError>
+where
+    E: Pairing,
+    QAP: R1CSToQAP,
+    C: ConstraintSynthesizer<E::ScalarField> + Clone,
+    R: RngCore + CryptoRng,
+{
+    let public_inputs = public_inputs::<E::ScalarField, _>(circuit.clone())?;
+    let proof = Groth16::<E, QAP>::prove(pk, circuit, rng)?;
+
+    let proof = Proof {
+        circuit_id,
+        a: compressed(&proof.a),
+        b: compressed(&proof.b),
+        c: compressed(&proof.c),
+        encoding: ProofEncoding::Compressed,
+    };
+    Ok(ProvenStatement { proof, public_inputs: be_bytes(&public_inputs) })
+}
+
+/// Check `proof` against `vk` and big-endian `public_inputs`.
+pub fn verify<E: Groth16Curve>(
+    vk: &VerifyingKey<E>,
+    proof: &Proof,
+    public_inputs: &[u8],
+) -> Result<bool, ProverError> {
+    let decoded = E::decode_proof(proof).map_err(ProverError::Proof)?;
+    let inputs = PublicInputs::<E::ScalarField>::from_be_bytes(public_inputs)
+        .map_err(ProverError::PublicInputs)?;
+    Ok(Groth16::<E>::verify(vk, inputs.as_slice(), &decoded)?)
+}
+
+/// Synthesize `circuit`, check that its witness satisfies it and return its
+/// public inputs.
+fn public_inputs<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<Vec<F>, ProverError> {
+    let cs = ConstraintSystem::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    if !cs.is_satisfied()? {
+        return Err(ProverError::Unsatisfied);
+    }
+    let cs = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+    // The first instance variable is the constant one.
+    Ok(cs.instance_assignment[1..].to_vec())
+}
+
+/// Concatenate `inputs` as big-endian elements of `field_size::<F>()` bytes.
+fn be_bytes<F: PrimeField>(inputs: &[F]) -> Vec<u8> {
+    let size = field_size::<F>();
+    let mut bytes = Vec::with_capacity(inputs.len() * size);
+    for input in inputs {
+        let element = input.into_bigint().to_bytes_be();
+        bytes.extend_from_slice(&element[element.len() - size..]);
+    }
+    bytes
+}
+
+fn compressed<P: CanonicalSerialize>(point: &P) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(point.compressed_size());
+    point
+        .serialize_compressed(&mut bytes)
+        .expect("writing to a Vec cannot fail; qed");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gadgets::EnhancedTransaction,
+        poseidon::{self, Poseidon},
+        utils::{
+            join_split::Note,
+            merkle::{MemoryStore, MerkleTree},
+        },
+    };
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::Zero;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    /// Depth of the test tree. Keys only fit trees of the depth they were
+    /// set up for, and a shallow one keeps the setup quick.
+    const LEVELS: usize = 4;
+
+    /// A transfer of a note worth 100 out of a tree holding only it.
+    fn transfer() -> EnhancedTransaction {
+        let (secret, nullifier) = (Fr::from(1u64), Fr::from(2u64));
+        let note = Note { asset: Fr::zero(), amount: 100, secret, nullifier };
+        let mut tree = MerkleTree::<Poseidon, _>::new(LEVELS as u32, MemoryStore::default())
+            .unwrap();
+        tree.insert(poseidon::to_h256(note.commitment())).unwrap();
+        EnhancedTransaction::spend(&tree, note, 0, 1_000, Fr::from(2u64)).unwrap()
+    }
+
+    #[test]
+    fn proves_and_verifies_a_transfer() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (pk, vk) = setup::<Bn254, _, _>(EnhancedTransaction::blank(LEVELS), &mut rng).unwrap();
+
+        let circuit = transfer();
+        let statement = prove(&pk, circuit.clone(), 4, &mut rng).unwrap();
+        assert_eq!(statement.proof.circuit_id, 4);
+        assert_eq!(statement.public_inputs, be_bytes(&circuit.public_signals()));
+        assert_eq!(verify(&vk, &statement.proof, &statement.public_inputs), Ok(true));
+
+        let mut other = circuit.public_signals();
+        other[4] += Fr::from(1u64);
+        assert_eq!(verify(&vk, &statement.proof, &be_bytes(&other)), Ok(false));
+    }
+
+    #[test]
+    fn refuses_to_prove_an_unsatisfied_witness() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (pk, _) = setup::<Bn254, _, _>(EnhancedTransaction::blank(LEVELS), &mut rng).unwrap();
+
+        let mut circuit = transfer();
+        circuit.max_amount = 99;
+        assert_eq!(prove(&pk, circuit, 4, &mut rng), Err(ProverError::Unsatisfied));
+
+        let mut circuit = transfer();
+        circuit.root += Fr::from(1u64);
+        assert_eq!(prove(&pk, circuit, 4, &mut rng), Err(ProverError::Unsatisfied));
+    }
+
+    #[test]
+    fn exported_keys_parse_back() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (_, vk) = setup::<Bn254, _, _>(EnhancedTransaction::blank(LEVELS), &mut rng).unwrap();
+        let json = Bn254::export_verification_key(&vk);
+        assert_eq!(Bn254::parse_verification_key(&json), Ok(vk));
+    }
+}
@@ -0,0 +1,48 @@
+//! Types for the on-chain verifying key registry.
+
+use crate::curve::Curve;
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    traits::Get, BoundedVec, CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+/// Identifies a circuit whose proofs the pallet can verify. A circuit keeps
+/// its ID across key rotations; each rotation bumps the key's version.
+pub type CircuitId = u32;
+
+/// Lifecycle of a registered verifying key.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub enum KeyStatus {
+    /// Proofs against the key are accepted.
+    Active,
+    /// Proofs are still accepted, but wallets should move to a newer circuit.
+    Deprecated,
+    /// Proofs against the key are rejected.
+    Retired,
+}
+
+impl KeyStatus {
+    /// Whether proofs may currently be verified against a key in this state.
+    pub fn accepts_proofs(&self) -> bool {
+        !matches!(self, KeyStatus::Retired)
+    }
+}
+
+/// A verifying key as stored in the registry.
+#[derive(
+    CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, MaxEncodedLen, RuntimeDebugNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(MaxKeyLength))]
+#[codec(mel_bound())]
+pub struct VerifyingKeyInfo<MaxKeyLength: Get<u32>> {
+    /// Starts at 1 and increases every time the circuit's key is replaced.
+    pub version: u32,
+    /// The pairing engine the key is defined over.
+    pub curve: Curve,
+    /// Where the key is in its lifecycle.
+    pub status: KeyStatus,
+    /// The prepared key, serialized with [`crate::vk::serialize_prepared`].
+    pub key: BoundedVec<u8, MaxKeyLength>,
+}
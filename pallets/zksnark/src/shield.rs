@@ -0,0 +1,51 @@
+//! Statements for moving value into and out of the shielded pool.
+//!
+//! A deposit proves with the `Shield` circuit that the new note commits to
+//! the asset and amount taken from the depositor. Its public signals are
+//!
+//! ```text
+//! commitment, asset, amount
+//! ```
+//!
+//! A withdrawal proves with the `Unshield` circuit that it spends a note in
+//! the tree worth `amount` of `asset`, and binds who is paid. Its public
+//! signals are
+//!
+//! ```text
+//! nullifierHash, root, asset, amount, recipient, relayer, fee
+//! ```
+
+use crate::transfer::{account_input, asset_input, u64_input};
+use codec::Encode;
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+/// Big-endian public inputs for the shield circuit.
+pub fn shield_inputs<AssetId: Encode>(commitment: &H256, asset: &AssetId, amount: u64) -> Vec<u8> {
+    let mut inputs = Vec::with_capacity(3 * 32);
+    inputs.extend_from_slice(commitment.as_bytes());
+    inputs.extend_from_slice(&asset_input(asset));
+    inputs.extend_from_slice(&u64_input(amount));
+    inputs
+}
+
+/// Big-endian public inputs for the unshield circuit.
+pub fn unshield_inputs<AccountId: Encode, AssetId: Encode>(
+    nullifier_hash: &H256,
+    root: &H256,
+    asset: &AssetId,
+    amount: u64,
+    recipient: &AccountId,
+    relayer: &AccountId,
+    fee: u64,
+) -> Vec<u8> {
+    let mut inputs = Vec::with_capacity(7 * 32);
+    inputs.extend_from_slice(nullifier_hash.as_bytes());
+    inputs.extend_from_slice(root.as_bytes());
+    inputs.extend_from_slice(&asset_input(asset));
+    inputs.extend_from_slice(&u64_input(amount));
+    inputs.extend_from_slice(&account_input(recipient));
+    inputs.extend_from_slice(&account_input(relayer));
+    inputs.extend_from_slice(&u64_input(fee));
+    inputs
+}
@@ -0,0 +1,1246 @@
+use crate::{
+    artifacts::Circuit,
+    circom::{
+        self, read_proving_key, read_witness, CalculatorError, CircomCircuit, R1cs,
+        WitnessCalculator,
+    },
+    gadgets::{EnhancedTransaction, Shield, Unshield},
+    mock::*,
+    poseidon::Poseidon,
+    prover, shield,
+    transfer::{account_input, asset_input},
+    utils::{
+        join_split::{InputNote, JoinSplitWitness, MerklePath, Note, WitnessError},
+        merkle::{MemoryStore, MerkleTree},
+    },
+    BatchFailure, CircuitId, Commitments, Curve, Error, Event, Groth16Curve, JoinSplit,
+    KeyStatus, KnownRoots, NextLeafIndex, NullifierSet, PointError, PoolNullifiers, PoolStatus,
+    Pools, Proof, ProofEncoding, ProofError, PublicInputError, RootHistory, Transfer, TreeHasher,
+    VerifyingKeys, ZkSnarkVerifier,
+};
+use frame_support::traits::fungible::{Inspect, NativeOrWithId};
+use sp_runtime::{DispatchError, DispatchResult, TokenError};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::ProvingKey;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use frame_support::{assert_noop, assert_ok, traits::Get};
+use serde_json::{json, Value};
+use sp_core::H256;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+/// The runtime's `MaxTransferAmount`, which is the circuit's `maxAmount`.
+const MAX_AMOUNT: u64 = 1_000;
+
+/// Circuit IDs the genesis keys are registered under. The join-split one
+/// is the runtime's `JoinSplitCircuitId`, and so on.
+const TRANSFER_CIRCUIT: CircuitId = 0;
+const JOIN_SPLIT_CIRCUIT: CircuitId = 1;
+const SHIELD_CIRCUIT: CircuitId = 2;
+const UNSHIELD_CIRCUIT: CircuitId = 3;
+
+const RECIPIENT: u64 = 2;
+const RELAYER: u64 = 3;
+const FEE: u64 = 10;
+
+/// The native token, as `Assets` names it.
+const NATIVE: NativeOrWithId<u32> = NativeOrWithId::Native;
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+fn h256(element: Fr) -> H256 {
+    H256::from_slice(&element.into_bigint().to_bytes_be())
+}
+
+/// The field element `account` is bound into a proof as.
+fn account(account: u64) -> Fr {
+    Fr::from_be_bytes_mod_order(&account_input(&account))
+}
+
+/// The field element `asset` is bound into a proof as.
+fn asset(asset: NativeOrWithId<u32>) -> Fr {
+    Fr::from_be_bytes_mod_order(&asset_input(&asset))
+}
+
+/// What `build.rs` made for a circuit besides its verification key.
+struct Compiled {
+    r1cs: R1cs<Fr>,
+    wasm: Vec<u8>,
+    pk: ProvingKey<Bn254>,
+}
+
+/// The outputs of `build.rs` for `circuit`. Reading a proving key takes a
+/// while in debug builds, so each circuit's are read once for every test.
+fn compiled(circuit: Circuit) -> &'static Compiled {
+    static COMPILED: [OnceLock<Compiled>; Circuit::ALL.len()] =
+        [const { OnceLock::new() }; Circuit::ALL.len()];
+    COMPILED[circuit as usize].get_or_init(|| {
+        let read = |path: PathBuf| std::fs::read(path).unwrap();
+        Compiled {
+            r1cs: R1cs::read(&read(circuit.r1cs_path())).unwrap(),
+            wasm: read(circuit.wasm_path()),
+            pk: read_proving_key(&read(circuit.zkey_path())).unwrap(),
+        }
+    })
+}
+
+/// The circuit ID `circuit`'s genesis key is registered under.
+fn circuit_id(circuit: Circuit) -> CircuitId {
+    match circuit {
+        Circuit::Transfer => TRANSFER_CIRCUIT,
+        Circuit::JoinSplit => JOIN_SPLIT_CIRCUIT,
+        Circuit::Shield => SHIELD_CIRCUIT,
+        Circuit::Unshield => UNSHIELD_CIRCUIT,
+    }
+}
+
+/// The witness `circuit`'s wasm computes for the circom `inputs`.
+fn witness(circuit: Circuit, inputs: &Value) -> Result<Vec<Fr>, CalculatorError> {
+    WitnessCalculator::new(&compiled(circuit).wasm)?.calculate(inputs)
+}
+
+/// The public signals of `circuit` for `inputs`, outputs first.
+fn public_signals(circuit: Circuit, inputs: &Value) -> Vec<Fr> {
+    let witness = witness(circuit, inputs).unwrap();
+    let circuit = CircomCircuit::new(&compiled(circuit).r1cs, witness).unwrap();
+    circuit.public_signals().unwrap().to_vec()
+}
+
+/// A proof of `circuit` for `inputs` against its genesis key, made from the
+/// witness of its wasm and the proving key of its `.zkey`. The rng is
+/// seeded, so the same inputs always get the same proof, and each is made
+/// once for every test.
+fn prove(circuit: Circuit, inputs: Value) -> Proof {
+    static PROOFS: Mutex<BTreeMap<String, Proof>> = Mutex::new(BTreeMap::new());
+    let key = format!("{}{inputs}", circuit.name());
+    let mut proofs = PROOFS.lock().unwrap();
+    let proof = proofs.entry(key).or_insert_with(|| {
+        let Compiled { r1cs, pk, .. } = compiled(circuit);
+        let witness = witness(circuit, &inputs).unwrap();
+        let assigned = CircomCircuit::new(r1cs, witness).unwrap();
+        circom::prove(pk, assigned, circuit_id(circuit), &mut rng()).unwrap().proof
+    });
+    proof.clone()
+}
+
+/// A field element as a circom input.
+fn field(element: Fr) -> Value {
+    Value::String(element.to_string())
+}
+
+/// The `(elements, indices)` inputs of a Merkle path.
+fn path_inputs(path: &MerklePath) -> (Value, Value) {
+    let elements = path.elements.iter().copied().map(field).collect();
+    let indices = path.indices.iter().map(|&index| u8::from(index)).collect();
+    (elements, indices)
+}
+
+/// The inputs of `enhanced_transaction.circom` for `spend`.
+fn transfer_inputs(spend: &EnhancedTransaction) -> Value {
+    let EnhancedTransaction { note, path, root, max_amount, recipient } = spend;
+    let (elements, indices) = path_inputs(path);
+    json!({
+        "asset": field(note.asset),
+        "amount": note.amount,
+        "nullifier": field(note.nullifier),
+        "secret": field(note.secret),
+        "merklePathElements": elements,
+        "merklePathIndices": indices,
+        "root": field(*root),
+        "maxAmount": max_amount,
+        "recipient": field(*recipient),
+    })
+}
+
+/// The inputs of `join_split.circom` for `witness`.
+fn join_split_inputs(witness: &JoinSplitWitness) -> Value {
+    let notes =
+        |notes: &[Note], value: fn(&Note) -> Value| -> Value { notes.iter().map(value).collect() };
+    let paths = witness.paths.iter().map(path_inputs);
+    let (elements, indices): (Vec<_>, Vec<_>) = paths.unzip();
+    let (inputs, outputs) = (&witness.inputs, &witness.outputs);
+    json!({
+        "inAsset": notes(inputs, |note| field(note.asset)),
+        "inAmount": notes(inputs, |note| note.amount.into()),
+        "inSecret": notes(inputs, |note| field(note.secret)),
+        "inNullifier": notes(inputs, |note| field(note.nullifier)),
+        "inPathElements": elements,
+        "inPathIndices": indices,
+        "outAsset": notes(outputs, |note| field(note.asset)),
+        "outAmount": notes(outputs, |note| note.amount.into()),
+        "outSecret": notes(outputs, |note| field(note.secret)),
+        "outNullifier": notes(outputs, |note| field(note.nullifier)),
+        "root": field(witness.root),
+        "relayer": field(witness.relayer),
+        "feeAsset": field(witness.fee_asset),
+        "fee": witness.fee,
+    })
+}
+
+/// The inputs of `shield.circom` for `deposit`.
+fn shield_inputs(deposit: &Shield) -> Value {
+    let note = &deposit.note;
+    json!({
+        "asset": field(note.asset),
+        "amount": note.amount,
+        "secret": field(note.secret),
+        "nullifier": field(note.nullifier),
+    })
+}
+
+/// The inputs of `unshield.circom` for `withdrawal`.
+fn unshield_inputs(withdrawal: &Unshield) -> Value {
+    let Unshield { note, path, root, recipient, relayer, fee } = withdrawal;
+    let (elements, indices) = path_inputs(path);
+    json!({
+        "secret": field(note.secret),
+        "nullifier": field(note.nullifier),
+        "pathElements": elements,
+        "pathIndices": indices,
+        "root": field(*root),
+        "asset": field(note.asset),
+        "amount": note.amount,
+        "recipient": field(*recipient),
+        "relayer": field(*relayer),
+        "fee": fee,
+    })
+}
+
+/// The note the transfer tests spend, worth the most a transfer may move.
+fn transfer_note() -> Note {
+    let (secret, nullifier) = (Fr::from(1u64), Fr::from(2u64));
+    Note { asset: asset(NATIVE), amount: MAX_AMOUNT, secret, nullifier }
+}
+
+/// Append `note` to the empty commitment tree. Returns a copy of the tree
+/// that keeps the paths.
+fn deposit(note: &Note) -> MerkleTree<Poseidon, MemoryStore> {
+    let commitment = h256(note.commitment());
+    ZkSnark::insert_leaf(commitment).unwrap();
+    let mut tree = MerkleTree::new(MerkleTreeDepth::get(), MemoryStore::default()).unwrap();
+    tree.insert(commitment).unwrap();
+    assert_eq!(ZkSnark::current_root(), Ok(tree.root()));
+    tree
+}
+
+/// Prove that `note`, the first leaf of `tree`, is spent to `RECIPIENT`
+/// under `max_amount`.
+fn prove_transfer(
+    tree: &MerkleTree<Poseidon, MemoryStore>,
+    note: Note,
+    max_amount: u64,
+) -> Transfer<u64> {
+    let spend = EnhancedTransaction::spend(tree, note, 0, max_amount, account(RECIPIENT)).unwrap();
+    Transfer {
+        proof: prove(Circuit::Transfer, transfer_inputs(&spend)),
+        nullifier_hash: h256(note.nullifier_hash()),
+        commitment: h256(note.commitment()),
+        root: tree.root(),
+        recipient: RECIPIENT,
+    }
+}
+
+/// Deposit [`transfer_note`] and prove its transfer within the limit.
+fn valid_transfer() -> Transfer<u64> {
+    let note = transfer_note();
+    let tree = deposit(&note);
+    prove_transfer(&tree, note, MAX_AMOUNT)
+}
+
+fn private_transfer(who: u64, transfer: Transfer<u64>) -> DispatchResult {
+    let Transfer { proof, nullifier_hash, commitment, root, recipient } = transfer;
+    ZkSnark::private_transfer(
+        RuntimeOrigin::signed(who),
+        proof,
+        nullifier_hash,
+        commitment,
+        root,
+        recipient,
+    )
+}
+
+#[test]
+fn private_transfer_within_limit_is_accepted() {
+    new_test_ext().execute_with(|| {
+        // End to end: the witness comes from the compiled circuit's wasm and
+        // the proof from its `.zkey`, and the pallet checks it against the
+        // key registered at genesis.
+        let transfer = valid_transfer();
+        let (nullifier_hash, commitment) = (transfer.nullifier_hash, transfer.commitment);
+
+        assert_ok!(private_transfer(1, transfer));
+        System::assert_last_event(
+            Event::PrivateTransfer {
+                who: 1,
+                nullifier_hash,
+                commitment,
+                leaf_index: 1,
+                recipient: RECIPIENT,
+            }
+            .into(),
+        );
+        assert!(NullifierSet::<Test>::contains_key(nullifier_hash));
+    });
+}
+
+#[test]
+fn private_transfer_over_limit_is_rejected() {
+    new_test_ext().execute_with(|| {
+        let note = Note { amount: MAX_AMOUNT + 1, ..transfer_note() };
+        let tree = deposit(&note);
+        let recipient = account(RECIPIENT);
+        assert_eq!(
+            EnhancedTransaction::spend(&tree, note, 0, MAX_AMOUNT, recipient),
+            Err(WitnessError::OutOfRange)
+        );
+
+        // Forcing the inputs fails the circuit's range check, so there is no
+        // witness to prove.
+        let mut spend =
+            EnhancedTransaction::spend(&tree, note, 0, MAX_AMOUNT + 1, recipient).unwrap();
+        spend.max_amount = MAX_AMOUNT;
+        assert!(matches!(
+            witness(Circuit::Transfer, &transfer_inputs(&spend)),
+            Err(CalculatorError::Exception { .. })
+        ));
+
+        // A proof under a higher limit is checked against the runtime's.
+        let transfer = prove_transfer(&tree, note, MAX_AMOUNT + 1);
+        assert_noop!(private_transfer(1, transfer), Error::<Test>::VerificationFailed);
+    });
+}
+
+#[test]
+fn private_transfer_for_another_circuit_is_rejected() {
+    new_test_ext().execute_with(|| {
+        let mut transfer = valid_transfer();
+        // The transfer key under the join-split ID, so only the ID is wrong.
+        assert_ok!(ZkSnark::register_verifying_key(
+            RuntimeOrigin::root(),
+            JOIN_SPLIT_CIRCUIT,
+            Circuit::Transfer.verification_key().to_vec()
+        ));
+        transfer.proof.circuit_id = JOIN_SPLIT_CIRCUIT;
+
+        assert_noop!(private_transfer(1, transfer.clone()), Error::<Test>::WrongCircuit);
+        assert_noop!(
+            ZkSnark::batch_private_transfer(
+                RuntimeOrigin::signed(1),
+                vec![transfer].try_into().unwrap()
+            ),
+            Error::<Test>::WrongCircuit
+        );
+    });
+}
+
+#[test]
+fn copied_proof_cannot_be_redirected() {
+    new_test_ext().execute_with(|| {
+        // A mempool observer swapping in their own account.
+        let transfer = Transfer { recipient: 4, ..valid_transfer() };
+        assert_noop!(private_transfer(4, transfer), Error::<Test>::VerificationFailed);
+    });
+}
+
+#[test]
+fn enhanced_transaction_signals_are_the_transfer_inputs() {
+    let (secret, nullifier) = (Fr::from(1u64), Fr::from(2u64));
+    let note = Note { asset: asset(NATIVE), amount: 100, secret, nullifier };
+    let mut tree = MerkleTree::<Poseidon, _>::new(20, MemoryStore::default()).unwrap();
+    tree.insert(h256(note.commitment())).unwrap();
+    let spend = EnhancedTransaction::spend(&tree, note, 0, MAX_AMOUNT, account(RECIPIENT)).unwrap();
+
+    let transfer = Transfer {
+        proof: Proof {
+            circuit_id: TRANSFER_CIRCUIT,
+            a: Vec::new(),
+            b: Vec::new(),
+            c: Vec::new(),
+            encoding: ProofEncoding::Compressed,
+        },
+        nullifier_hash: h256(note.nullifier_hash()),
+        commitment: h256(note.commitment()),
+        root: tree.root(),
+        recipient: RECIPIENT,
+    };
+    let signals = public_signals(Circuit::Transfer, &transfer_inputs(&spend));
+    assert_eq!(signals, spend.public_signals());
+    let signals: Vec<u8> = signals.into_iter().flat_map(|signal| h256(signal).0).collect();
+    assert_eq!(transfer.public_inputs(MAX_AMOUNT), signals);
+}
+
+/// A native note worth `amount`, with secrets derived from `seed`.
+fn native_note(amount: u64, seed: u64) -> Note {
+    let (secret, nullifier) = (Fr::from(seed), Fr::from(seed + 1_000));
+    Note { asset: asset(NATIVE), amount, secret, nullifier }
+}
+
+/// Append notes worth 100 and 50 to the commitment tree and move their
+/// worth into the pool, as shielding them would have. Returns a proof
+/// spending them into notes worth 120 and 20, with `FEE` for `RELAYER`.
+fn valid_join_split() -> JoinSplit<u64, NativeOrWithId<u32>> {
+    let notes = [native_note(100, 11), native_note(50, 12)];
+    let mut tree = MerkleTree::new(MerkleTreeDepth::get(), MemoryStore::default()).unwrap();
+    for note in &notes {
+        let commitment = h256(note.commitment());
+        ZkSnark::insert_leaf(commitment).unwrap();
+        tree.insert(commitment).unwrap();
+    }
+    let pool = ZkSnark::account_id();
+    assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(1), pool, 150));
+
+    let inputs = [0, 1].map(|i| InputNote { note: notes[i], leaf_index: Some(i as u32) });
+    let outputs = [native_note(120, 13), native_note(20, 14)];
+    let (relayer, fee_asset) = (account(RELAYER), asset(NATIVE));
+    let witness = JoinSplitWitness::new(&tree, inputs, outputs, relayer, fee_asset, FEE).unwrap();
+    JoinSplit {
+        proof: prove(Circuit::JoinSplit, join_split_inputs(&witness)),
+        root: tree.root(),
+        nullifier_hashes: witness.inputs.map(|note| h256(note.nullifier_hash())),
+        commitments: witness.outputs.map(|note| h256(note.commitment())),
+        relayer: RELAYER,
+        fee_asset: NATIVE,
+        fee: FEE,
+    }
+}
+
+fn join_split(who: u64, statement: JoinSplit<u64, NativeOrWithId<u32>>) -> DispatchResult {
+    let JoinSplit { proof, root, nullifier_hashes, commitments, relayer, fee_asset, fee } =
+        statement;
+    ZkSnark::join_split(
+        RuntimeOrigin::signed(who),
+        proof,
+        root,
+        nullifier_hashes,
+        commitments,
+        relayer,
+        fee_asset,
+        fee,
+    )
+}
+
+#[test]
+fn join_split_records_both_nullifiers_and_commitments() {
+    new_test_ext().execute_with(|| {
+        let statement = valid_join_split();
+        let JoinSplit { nullifier_hashes, commitments, .. } = statement.clone();
+        let pool = ZkSnark::account_id();
+        let pool_balance = Balances::balance(&pool);
+
+        assert_ok!(join_split(1, statement));
+        System::assert_last_event(
+            Event::JoinSplit {
+                who: 1,
+                nullifier_hashes,
+                commitments,
+                leaf_indices: [2, 3],
+                relayer: RELAYER,
+                fee_asset: NATIVE,
+                fee: FEE,
+            }
+            .into(),
+        );
+        assert!(nullifier_hashes.iter().all(NullifierSet::<Test>::contains_key));
+        assert!(commitments.iter().all(Commitments::<Test>::contains_key));
+        assert_eq!(NextLeafIndex::<Test>::get(), 4);
+
+        // The fee leaves the pool for the relayer.
+        assert_eq!(Balances::balance(&RELAYER), INITIAL_BALANCE + FEE);
+        assert_eq!(Balances::balance(&pool), pool_balance - FEE);
+    });
+}
+
+#[test]
+fn join_split_fee_cannot_be_redirected() {
+    new_test_ext().execute_with(|| {
+        let statement = valid_join_split();
+        for changed in [
+            JoinSplit { relayer: 4, ..statement.clone() },
+            JoinSplit { fee: FEE + 1, ..statement.clone() },
+        ] {
+            assert_noop!(join_split(4, changed), Error::<Test>::VerificationFailed);
+        }
+    });
+}
+
+#[test]
+fn join_split_cannot_spend_a_nullifier_twice() {
+    new_test_ext().execute_with(|| {
+        let statement = valid_join_split();
+        let [first, _] = statement.nullifier_hashes;
+        let doubled = JoinSplit { nullifier_hashes: [first, first], ..statement.clone() };
+        assert_noop!(join_split(1, doubled), Error::<Test>::NullifierAlreadyUsed);
+
+        assert_ok!(join_split(1, statement.clone()));
+        assert_noop!(join_split(1, statement), Error::<Test>::NullifierAlreadyUsed);
+    });
+}
+
+#[test]
+fn join_split_rejects_proofs_for_other_circuits() {
+    new_test_ext().execute_with(|| {
+        let transfer = valid_transfer();
+
+        // Without the circuit check this would fail in the pairing check
+        // instead.
+        assert_noop!(
+            ZkSnark::join_split(
+                RuntimeOrigin::signed(1),
+                transfer.proof,
+                transfer.root,
+                [transfer.nullifier_hash, h256(Fr::from(8u64))],
+                [transfer.commitment, h256(Fr::from(9u64))],
+                RELAYER,
+                NATIVE,
+                FEE,
+            ),
+            Error::<Test>::WrongCircuit
+        );
+    });
+}
+
+const SHIELDED: u64 = 100;
+
+/// The note the shield and unshield tests deposit, worth `SHIELDED` of
+/// `note_asset`.
+fn shielded_note(note_asset: NativeOrWithId<u32>) -> Note {
+    let (secret, nullifier) = (Fr::from(21u64), Fr::from(22u64));
+    Note { asset: asset(note_asset), amount: SHIELDED, secret, nullifier }
+}
+
+/// Prove a deposit of [`shielded_note`]. Returns the note's commitment and
+/// the proof.
+fn shield_proof(note_asset: NativeOrWithId<u32>) -> (H256, Proof) {
+    let note = shielded_note(note_asset);
+    (h256(note.commitment()), prove(Circuit::Shield, shield_inputs(&Shield { note })))
+}
+
+#[test]
+fn shield_moves_funds_into_the_pool() {
+    new_test_ext().execute_with(|| {
+        let (commitment, proof) = shield_proof(NATIVE);
+        let pool = ZkSnark::account_id();
+        let pool_balance = Balances::balance(&pool);
+
+        assert_ok!(ZkSnark::shield(RuntimeOrigin::signed(1), NATIVE, SHIELDED, commitment, proof));
+
+        assert_eq!(Balances::balance(&1), INITIAL_BALANCE - SHIELDED);
+        assert_eq!(Balances::balance(&pool), pool_balance + SHIELDED);
+        assert!(Commitments::<Test>::contains_key(commitment));
+        System::assert_last_event(
+            Event::Shielded { who: 1, asset: NATIVE, amount: SHIELDED, commitment, leaf_index: 0 }
+                .into(),
+        );
+    });
+}
+
+#[test]
+fn shield_amount_must_match_the_note() {
+    new_test_ext().execute_with(|| {
+        let (commitment, proof) = shield_proof(NATIVE);
+        for (asset, amount) in [(NATIVE, SHIELDED + 1), (NativeOrWithId::WithId(7), SHIELDED)] {
+            assert_noop!(
+                ZkSnark::shield(RuntimeOrigin::signed(1), asset, amount, commitment, proof.clone()),
+                Error::<Test>::VerificationFailed
+            );
+        }
+    });
+}
+
+#[test]
+fn shield_needs_a_transparent_balance() {
+    new_test_ext().execute_with(|| {
+        let (commitment, proof) = shield_proof(NATIVE);
+        assert_noop!(
+            ZkSnark::shield(RuntimeOrigin::signed(5), NATIVE, SHIELDED, commitment, proof),
+            TokenError::FundsUnavailable
+        );
+    });
+}
+
+#[test]
+fn shield_rejects_proofs_for_other_circuits() {
+    new_test_ext().execute_with(|| {
+        let (commitment, mut proof) = shield_proof(NATIVE);
+        proof.circuit_id = JOIN_SPLIT_CIRCUIT;
+        assert_noop!(
+            ZkSnark::shield(RuntimeOrigin::signed(1), NATIVE, SHIELDED, commitment, proof),
+            Error::<Test>::WrongCircuit
+        );
+    });
+}
+
+#[test]
+fn shield_signals_are_the_shield_inputs() {
+    let deposit = Shield { note: shielded_note(NATIVE) };
+    let signals = public_signals(Circuit::Shield, &shield_inputs(&deposit));
+    assert_eq!(signals, deposit.public_signals());
+    let signals: Vec<u8> = signals.into_iter().flat_map(|signal| h256(signal).0).collect();
+    let commitment = h256(deposit.note.commitment());
+    assert_eq!(shield::shield_inputs(&commitment, &NATIVE, SHIELDED), signals);
+}
+
+/// A tree holding only `note`, as the shielded pool's or a fixed
+/// denomination pool's is after its first deposit.
+fn tree_of(note: &Note) -> MerkleTree<Poseidon, MemoryStore> {
+    let mut tree = MerkleTree::new(MerkleTreeDepth::get(), MemoryStore::default()).unwrap();
+    tree.insert(h256(note.commitment())).unwrap();
+    tree
+}
+
+/// Prove a withdrawal of `note`, the only leaf of its tree, to `RECIPIENT`
+/// with `FEE` for `RELAYER`. Returns the tree's root, the nullifier hash
+/// and the proof.
+fn prove_unshield(note: Note) -> (H256, H256, Proof) {
+    let tree = tree_of(&note);
+    let withdrawal =
+        Unshield::withdraw(&tree, note, 0, account(RECIPIENT), account(RELAYER), FEE).unwrap();
+    let proof = prove(Circuit::Unshield, unshield_inputs(&withdrawal));
+    (tree.root(), h256(note.nullifier_hash()), proof)
+}
+
+/// Shield [`shielded_note`] from account 1 and prove a withdrawal of it.
+/// Returns the tree root after the deposit, the nullifier hash and the
+/// proof.
+fn shield_then_prove_unshield() -> (H256, H256, Proof) {
+    let (commitment, proof) = shield_proof(NATIVE);
+    assert_ok!(ZkSnark::shield(RuntimeOrigin::signed(1), NATIVE, SHIELDED, commitment, proof));
+
+    let (root, nullifier_hash, proof) = prove_unshield(shielded_note(NATIVE));
+    assert_eq!(RootHistory::<Test>::get(0), Some(root));
+    (root, nullifier_hash, proof)
+}
+
+#[test]
+fn unshield_pays_the_recipient_and_relayer() {
+    new_test_ext().execute_with(|| {
+        let pool = ZkSnark::account_id();
+        let pool_balance = Balances::balance(&pool);
+        let (root, nullifier_hash, proof) = shield_then_prove_unshield();
+
+        // Submitted by an unrelated account; the recipient's balance is
+        // irrelevant to the withdrawal.
+        assert_ok!(ZkSnark::unshield(
+            RuntimeOrigin::signed(4),
+            proof,
+            nullifier_hash,
+            root,
+            NATIVE,
+            SHIELDED,
+            RECIPIENT,
+            RELAYER,
+            FEE,
+        ));
+
+        assert_eq!(Balances::balance(&RECIPIENT), INITIAL_BALANCE + SHIELDED - FEE);
+        assert_eq!(Balances::balance(&RELAYER), INITIAL_BALANCE + FEE);
+        assert_eq!(Balances::balance(&pool), pool_balance);
+        assert!(NullifierSet::<Test>::contains_key(nullifier_hash));
+        System::assert_last_event(
+            Event::Unshielded {
+                nullifier_hash,
+                asset: NATIVE,
+                amount: SHIELDED,
+                recipient: RECIPIENT,
+                relayer: RELAYER,
+                fee: FEE,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn unshield_cannot_spend_a_note_twice() {
+    new_test_ext().execute_with(|| {
+        let (root, nullifier_hash, proof) = shield_then_prove_unshield();
+        let unshield = || {
+            ZkSnark::unshield(
+                RuntimeOrigin::signed(4),
+                proof.clone(),
+                nullifier_hash,
+                root,
+                NATIVE,
+                SHIELDED,
+                RECIPIENT,
+                RELAYER,
+                FEE,
+            )
+        };
+
+        assert_ok!(unshield());
+        assert_noop!(unshield(), Error::<Test>::NullifierAlreadyUsed);
+    });
+}
+
+#[test]
+fn unshield_payout_cannot_be_redirected() {
+    new_test_ext().execute_with(|| {
+        let (root, nullifier_hash, proof) = shield_then_prove_unshield();
+
+        for (asset, amount, recipient, relayer, fee) in [
+            (NativeOrWithId::WithId(7), SHIELDED, RECIPIENT, RELAYER, FEE),
+            (NATIVE, SHIELDED + 1, RECIPIENT, RELAYER, FEE),
+            (NATIVE, SHIELDED, 4, RELAYER, FEE),
+            (NATIVE, SHIELDED, RECIPIENT, 4, FEE),
+            (NATIVE, SHIELDED, RECIPIENT, RELAYER, FEE + 1),
+        ] {
+            assert_noop!(
+                ZkSnark::unshield(
+                    RuntimeOrigin::signed(4),
+                    proof.clone(),
+                    nullifier_hash,
+                    root,
+                    asset,
+                    amount,
+                    recipient,
+                    relayer,
+                    fee,
+                ),
+                Error::<Test>::VerificationFailed
+            );
+        }
+    });
+}
+
+#[test]
+fn unshield_fee_cannot_exceed_the_amount() {
+    let note = shielded_note(NATIVE);
+    let tree = tree_of(&note);
+    let (recipient, relayer) = (account(RECIPIENT), account(RELAYER));
+    assert_eq!(
+        Unshield::withdraw(&tree, note, 0, recipient, relayer, SHIELDED + 1),
+        Err(WitnessError::OutOfRange)
+    );
+
+    // Forcing the inputs fails the circuit's fee check, so there is no
+    // witness to prove.
+    let mut withdrawal = Unshield::withdraw(&tree, note, 0, recipient, relayer, FEE).unwrap();
+    withdrawal.fee = SHIELDED + 1;
+    assert!(matches!(
+        witness(Circuit::Unshield, &unshield_inputs(&withdrawal)),
+        Err(CalculatorError::Exception { .. })
+    ));
+
+    new_test_ext().execute_with(|| {
+        let (root, nullifier_hash, proof) = shield_then_prove_unshield();
+        assert_noop!(
+            ZkSnark::unshield(
+                RuntimeOrigin::signed(4),
+                proof,
+                nullifier_hash,
+                root,
+                NATIVE,
+                SHIELDED,
+                RECIPIENT,
+                RELAYER,
+                SHIELDED + 1,
+            ),
+            Error::<Test>::FeeExceedsAmount
+        );
+    });
+}
+
+#[test]
+fn the_pool_holds_issued_assets_alongside_the_native_token() {
+    new_test_ext().execute_with(|| {
+        // An issued asset that is not sufficient, which the note takes out
+        // of the pool in full.
+        let token = NativeOrWithId::WithId(7);
+        let pool = ZkSnark::account_id();
+        assert_ok!(Assets::force_create(RuntimeOrigin::root(), 7, 1, false, 1));
+        assert_ok!(Assets::mint(RuntimeOrigin::signed(1), 7, 1, 1_000));
+
+        let (commitment, proof) = shield_proof(token.clone());
+        assert_noop!(
+            ZkSnark::shield(RuntimeOrigin::signed(1), NATIVE, SHIELDED, commitment, proof.clone()),
+            Error::<Test>::VerificationFailed
+        );
+        assert_ok!(ZkSnark::shield(
+            RuntimeOrigin::signed(1),
+            token.clone(),
+            SHIELDED,
+            commitment,
+            proof
+        ));
+        assert_eq!(Assets::balance(7, 1), 1_000 - SHIELDED);
+        assert_eq!(Assets::balance(7, pool), SHIELDED);
+
+        let (root, nullifier_hash, proof) = prove_unshield(shielded_note(token.clone()));
+        assert_eq!(RootHistory::<Test>::get(0), Some(root));
+        let unshield = |asset| {
+            ZkSnark::unshield(
+                RuntimeOrigin::signed(4),
+                proof.clone(),
+                nullifier_hash,
+                root,
+                asset,
+                SHIELDED,
+                RECIPIENT,
+                RELAYER,
+                FEE,
+            )
+        };
+        assert_noop!(unshield(NATIVE), Error::<Test>::VerificationFailed);
+        assert_ok!(unshield(token));
+
+        assert_eq!(Assets::balance(7, RECIPIENT), SHIELDED - FEE);
+        assert_eq!(Assets::balance(7, RELAYER), FEE);
+        assert_eq!(Assets::balance(7, pool), 0);
+        assert_eq!(Balances::balance(&RECIPIENT), INITIAL_BALANCE);
+    });
+}
+
+/// The denomination of the native pool most pool tests use.
+const DENOMINATION: u64 = 100;
+
+/// The latest root of the pool for `asset` and `denomination`.
+fn pool_root(asset: NativeOrWithId<u32>, denomination: u64) -> H256 {
+    let pool = Pools::<Test>::get((asset, denomination)).unwrap();
+    *pool.roots.last().unwrap()
+}
+
+/// Create the native pool, deposit a note into it from account 1 and prove
+/// a withdrawal of the note. Returns the pool's root, the nullifier hash
+/// and the proof.
+fn deposit_then_prove_withdrawal() -> (H256, H256, Proof) {
+    let (secret, nullifier) = (Fr::from(31u64), Fr::from(32u64));
+    let note = Note { asset: asset(NATIVE), amount: DENOMINATION, secret, nullifier };
+    assert_ok!(ZkSnark::create_pool(RuntimeOrigin::root(), NATIVE, DENOMINATION));
+    let commitment = h256(note.commitment());
+    assert_ok!(ZkSnark::pool_deposit(RuntimeOrigin::signed(1), NATIVE, DENOMINATION, commitment));
+
+    let (root, nullifier_hash, proof) = prove_unshield(note);
+    assert_eq!(pool_root(NATIVE, DENOMINATION), root);
+    (root, nullifier_hash, proof)
+}
+
+fn withdraw(root: H256, nullifier_hash: H256, proof: Proof) -> DispatchResult {
+    ZkSnark::pool_withdraw(
+        RuntimeOrigin::signed(4),
+        NATIVE,
+        DENOMINATION,
+        proof,
+        nullifier_hash,
+        root,
+        RECIPIENT,
+        RELAYER,
+        FEE,
+    )
+}
+
+#[test]
+fn pools_are_created_by_the_pool_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ZkSnark::create_pool(RuntimeOrigin::signed(1), NATIVE, DENOMINATION),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            ZkSnark::create_pool(RuntimeOrigin::root(), NATIVE, 0),
+            Error::<Test>::DenominationBelowMinimum
+        );
+
+        assert_ok!(ZkSnark::create_pool(RuntimeOrigin::root(), NATIVE, DENOMINATION));
+        System::assert_last_event(
+            Event::PoolCreated { asset: NATIVE, denomination: DENOMINATION }.into(),
+        );
+        assert_noop!(
+            ZkSnark::create_pool(RuntimeOrigin::root(), NATIVE, DENOMINATION),
+            Error::<Test>::PoolExists
+        );
+        assert_noop!(
+            ZkSnark::pool_deposit(RuntimeOrigin::signed(1), NATIVE, 50, H256::zero()),
+            Error::<Test>::UnknownPool
+        );
+        assert_noop!(
+            ZkSnark::pool_deposit(
+                RuntimeOrigin::signed(1),
+                NATIVE,
+                DENOMINATION,
+                H256::repeat_byte(0xff)
+            ),
+            Error::<Test>::NonCanonicalCommitment
+        );
+    });
+}
+
+#[test]
+fn each_pool_keeps_its_own_tree() {
+    new_test_ext().execute_with(|| {
+        // An issued asset that is not sufficient: the pool account can only
+        // hold it because the pool keeps the account alive.
+        let asset = NativeOrWithId::WithId(7);
+        assert_ok!(Assets::force_create(RuntimeOrigin::root(), 7, 1, false, 1));
+        assert_ok!(Assets::mint(RuntimeOrigin::signed(1), 7, 1, 1_000));
+        assert_ok!(ZkSnark::create_pool(RuntimeOrigin::root(), NATIVE, DENOMINATION));
+        assert_ok!(ZkSnark::create_pool(RuntimeOrigin::root(), asset.clone(), 50));
+
+        let (first, second) = (h256(Fr::from(5u64)), h256(Fr::from(6u64)));
+        assert_ok!(ZkSnark::pool_deposit(RuntimeOrigin::signed(1), NATIVE, DENOMINATION, first));
+        assert_ok!(ZkSnark::pool_deposit(RuntimeOrigin::signed(1), asset.clone(), 50, second));
+        let deposit = Event::PoolDeposit {
+            who: 1,
+            asset: asset.clone(),
+            denomination: 50,
+            commitment: second,
+            leaf_index: 0,
+        };
+        System::assert_last_event(deposit.into());
+
+        for (asset, denomination, commitment) in
+            [(NATIVE, DENOMINATION, first), (asset, 50, second)]
+        {
+            let mut tree = MerkleTree::<Poseidon, _>::new(20, MemoryStore::default()).unwrap();
+            tree.insert(commitment).unwrap();
+            assert_eq!(pool_root(asset.clone(), denomination), tree.root());
+
+            let account = ZkSnark::pool_account_id(&(asset.clone(), denomination));
+            assert_eq!(NativeAndAssets::balance(asset, &account), denomination);
+        }
+        assert_eq!(NextLeafIndex::<Test>::get(), 0);
+        assert_eq!(Assets::balance(7, 1), 950);
+    });
+}
+
+#[test]
+fn pool_withdraw_pays_out_the_denomination_once() {
+    new_test_ext().execute_with(|| {
+        let (root, nullifier_hash, proof) = deposit_then_prove_withdrawal();
+
+        assert_ok!(withdraw(root, nullifier_hash, proof.clone()));
+        assert_eq!(Balances::balance(&RECIPIENT), INITIAL_BALANCE + DENOMINATION - FEE);
+        assert_eq!(Balances::balance(&RELAYER), INITIAL_BALANCE + FEE);
+        let account = ZkSnark::pool_account_id(&(NATIVE, DENOMINATION));
+        assert_eq!(Balances::balance(&account), 0);
+        // The emptied account is kept alive by the pool.
+        assert_eq!(System::providers(&account), 1);
+        assert!(PoolNullifiers::<Test>::contains_key((NATIVE, DENOMINATION), nullifier_hash));
+        assert!(!NullifierSet::<Test>::contains_key(nullifier_hash));
+
+        assert_noop!(withdraw(root, nullifier_hash, proof), Error::<Test>::NullifierAlreadyUsed);
+    });
+}
+
+#[test]
+fn closed_pools_still_pay_out() {
+    new_test_ext().execute_with(|| {
+        let (root, nullifier_hash, proof) = deposit_then_prove_withdrawal();
+        let close = |origin| {
+            ZkSnark::set_pool_status(origin, NATIVE, DENOMINATION, PoolStatus::Closed)
+        };
+        assert_noop!(close(RuntimeOrigin::signed(1)), DispatchError::BadOrigin);
+        assert_ok!(close(RuntimeOrigin::root()));
+
+        assert_noop!(
+            ZkSnark::pool_deposit(RuntimeOrigin::signed(1), NATIVE, DENOMINATION, H256::zero()),
+            Error::<Test>::PoolClosed
+        );
+        assert_ok!(withdraw(root, nullifier_hash, proof));
+    });
+}
+
+#[test]
+fn pool_roots_are_not_shared() {
+    new_test_ext().execute_with(|| {
+        let (root, nullifier_hash, proof) = deposit_then_prove_withdrawal();
+        assert_ok!(ZkSnark::create_pool(RuntimeOrigin::root(), NATIVE, 2 * DENOMINATION));
+
+        assert_noop!(
+            ZkSnark::pool_withdraw(
+                RuntimeOrigin::signed(4),
+                NATIVE,
+                2 * DENOMINATION,
+                proof.clone(),
+                nullifier_hash,
+                root,
+                RECIPIENT,
+                RELAYER,
+                FEE,
+            ),
+            Error::<Test>::UnknownRoot
+        );
+        assert_noop!(
+            ZkSnark::unshield(
+                RuntimeOrigin::signed(4),
+                proof,
+                nullifier_hash,
+                root,
+                NATIVE,
+                DENOMINATION,
+                RECIPIENT,
+                RELAYER,
+                FEE,
+            ),
+            Error::<Test>::UnknownRoot
+        );
+    });
+}
+
+#[test]
+fn native_prover_proofs_are_accepted() {
+    new_test_ext().execute_with(|| {
+        // A shallow tree keeps the setup quick; the key only fits its depth.
+        let (circuit_id, levels) = (9, 4);
+        let blank = EnhancedTransaction::blank(levels);
+        let (pk, vk) = prover::setup::<Bn254, _, _>(blank, &mut rng()).unwrap();
+        assert_ok!(ZkSnark::register_verifying_key(
+            RuntimeOrigin::root(),
+            circuit_id,
+            Bn254::export_verification_key(&vk)
+        ));
+
+        let (secret, nullifier) = (Fr::from(1u64), Fr::from(2u64));
+        let note = Note { asset: asset(NATIVE), amount: 100, secret, nullifier };
+        let mut tree =
+            MerkleTree::<Poseidon, _>::new(levels as u32, MemoryStore::default()).unwrap();
+        tree.insert(h256(note.commitment())).unwrap();
+        let circuit =
+            EnhancedTransaction::spend(&tree, note, 0, MAX_AMOUNT, account(RECIPIENT)).unwrap();
+        let statement = prover::prove(&pk, circuit, circuit_id, &mut rng()).unwrap();
+        let proof = &statement.proof;
+        assert_eq!(ZkSnark::verify_proof(proof, &statement.public_inputs), Ok(true));
+
+        let mut inputs = statement.public_inputs.clone();
+        inputs[31] ^= 1;
+        assert_eq!(ZkSnark::verify_proof(proof, &inputs), Ok(false));
+    });
+}
+
+/// Circuit ID the circom multiplier's key is registered under.
+const MULTIPLIER_CIRCUIT: CircuitId = 5;
+
+fn register_multiplier() {
+    assert_ok!(ZkSnark::register_verifying_key(
+        RuntimeOrigin::root(),
+        MULTIPLIER_CIRCUIT,
+        include_bytes!("circuits/test/multiplier_verification_key.json").to_vec()
+    ));
+}
+
+/// A proof of the circom multiplier's witness against its snarkjs key, and
+/// the public inputs it proves.
+fn multiplier_proof(seed: u64) -> (Proof, Vec<u8>) {
+    let r1cs = R1cs::read(include_bytes!("circuits/test/multiplier.r1cs")).unwrap();
+    let witness = read_witness(include_bytes!("circuits/test/multiplier.wtns")).unwrap();
+    let pk = read_proving_key::<Bn254>(include_bytes!("circuits/test/multiplier_final.zkey"))
+        .unwrap();
+
+    let circuit = CircomCircuit::new(&r1cs, witness).unwrap();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let statement = circom::prove(&pk, circuit, MULTIPLIER_CIRCUIT, &mut rng).unwrap();
+    (statement.proof, statement.public_inputs)
+}
+
+#[test]
+fn proof_and_input_errors_map_to_pallet_errors() {
+    let cases: [(Error<Test>, Error<Test>); 10] = [
+        (ProofError::G1(PointError::InvalidLength).into(), Error::MalformedG1),
+        (ProofError::G1(PointError::NonCanonicalCoordinate).into(), Error::MalformedG1),
+        (ProofError::G2(PointError::InvalidEncoding).into(), Error::MalformedG2),
+        (ProofError::G1(PointError::NotOnCurve).into(), Error::PointNotOnCurve),
+        (ProofError::G2(PointError::NotInSubgroup).into(), Error::PointNotInSubgroup),
+        (ProofError::UnexpectedData.into(), Error::MalformedProofJson),
+        (ProofError::CurveMismatch.into(), Error::ProofCurveMismatch),
+        (PublicInputError::InvalidLength.into(), Error::MalformedPublicInputs),
+        (PublicInputError::OutOfRange.into(), Error::PublicInputOutOfRange),
+        (PublicInputError::TooMany.into(), Error::WrongPublicInputCount),
+    ];
+    for (error, expected) in cases {
+        assert_eq!(DispatchError::from(error), DispatchError::from(expected));
+    }
+}
+
+#[test]
+fn malformed_statements_report_typed_errors() {
+    new_test_ext().execute_with(|| {
+        register_multiplier();
+        let (proof, inputs) = multiplier_proof(0);
+        assert_eq!(ZkSnark::verify_proof(&proof, &inputs), Ok(true));
+
+        let mut truncated = proof.clone();
+        truncated.a.pop();
+        assert_eq!(
+            ZkSnark::verify_proof(&truncated, &inputs),
+            Err(Error::<Test>::MalformedG1.into())
+        );
+        assert_eq!(
+            ZkSnark::verify_proof(&proof, &inputs[..32]),
+            Err(Error::<Test>::WrongPublicInputCount.into())
+        );
+        assert_eq!(
+            ZkSnark::verify_proof(&proof, &inputs[1..]),
+            Err(Error::<Test>::MalformedPublicInputs.into())
+        );
+        let modulus = Fr::MODULUS.to_bytes_be();
+        assert_eq!(
+            ZkSnark::verify_proof(&proof, &[&inputs[..32], &modulus].concat()),
+            Err(Error::<Test>::PublicInputOutOfRange.into())
+        );
+    });
+}
+
+/// Status and version of the multiplier's registered key.
+fn multiplier_key() -> Option<(KeyStatus, u32)> {
+    VerifyingKeys::<Test>::get(MULTIPLIER_CIRCUIT).map(|info| (info.status, info.version))
+}
+
+#[test]
+fn registering_again_replaces_the_key_and_bumps_its_version() {
+    new_test_ext().execute_with(|| {
+        register_multiplier();
+        assert_eq!(multiplier_key(), Some((KeyStatus::Active, 1)));
+        assert_ok!(ZkSnark::deprecate_verifying_key(RuntimeOrigin::root(), MULTIPLIER_CIRCUIT));
+
+        register_multiplier();
+        assert_eq!(multiplier_key(), Some((KeyStatus::Active, 2)));
+        System::assert_last_event(
+            Event::VerifyingKeyRegistered {
+                circuit_id: MULTIPLIER_CIRCUIT,
+                version: 2,
+                curve: Curve::Bn254,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn deprecated_keys_still_verify() {
+    new_test_ext().execute_with(|| {
+        register_multiplier();
+        assert_ok!(ZkSnark::deprecate_verifying_key(RuntimeOrigin::root(), MULTIPLIER_CIRCUIT));
+        System::assert_last_event(
+            Event::VerifyingKeyDeprecated { circuit_id: MULTIPLIER_CIRCUIT, version: 1 }.into(),
+        );
+        assert_eq!(multiplier_key(), Some((KeyStatus::Deprecated, 1)));
+
+        let (proof, inputs) = multiplier_proof(0);
+        assert_eq!(ZkSnark::verify_proof(&proof, &inputs), Ok(true));
+        assert_noop!(
+            ZkSnark::deprecate_verifying_key(RuntimeOrigin::root(), MULTIPLIER_CIRCUIT),
+            Error::<Test>::InvalidKeyStatus
+        );
+    });
+}
+
+#[test]
+fn retired_keys_reject_proofs_until_replaced() {
+    new_test_ext().execute_with(|| {
+        register_multiplier();
+        let (proof, inputs) = multiplier_proof(0);
+        assert_ok!(ZkSnark::retire_verifying_key(RuntimeOrigin::root(), MULTIPLIER_CIRCUIT));
+        System::assert_last_event(
+            Event::VerifyingKeyRetired { circuit_id: MULTIPLIER_CIRCUIT, version: 1 }.into(),
+        );
+
+        assert_eq!(
+            ZkSnark::verify_proof(&proof, &inputs),
+            Err(Error::<Test>::VerifyingKeyRetired.into())
+        );
+        for transition in [ZkSnark::deprecate_verifying_key, ZkSnark::retire_verifying_key] {
+            assert_noop!(
+                transition(RuntimeOrigin::root(), MULTIPLIER_CIRCUIT),
+                Error::<Test>::InvalidKeyStatus
+            );
+        }
+
+        register_multiplier();
+        assert_eq!(multiplier_key(), Some((KeyStatus::Active, 2)));
+        assert_eq!(ZkSnark::verify_proof(&proof, &inputs), Ok(true));
+    });
+}
+
+#[test]
+fn the_registry_is_changed_by_the_registry_origin_only() {
+    new_test_ext().execute_with(|| {
+        let key = include_bytes!("circuits/test/multiplier_verification_key.json").to_vec();
+        let signed = || RuntimeOrigin::signed(1);
+        assert_noop!(
+            ZkSnark::register_verifying_key(signed(), MULTIPLIER_CIRCUIT, key),
+            DispatchError::BadOrigin
+        );
+
+        register_multiplier();
+        assert_noop!(
+            ZkSnark::deprecate_verifying_key(signed(), MULTIPLIER_CIRCUIT),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            ZkSnark::retire_verifying_key(signed(), MULTIPLIER_CIRCUIT),
+            DispatchError::BadOrigin
+        );
+        assert_eq!(multiplier_key(), Some((KeyStatus::Active, 1)));
+
+        assert_noop!(
+            ZkSnark::retire_verifying_key(RuntimeOrigin::root(), MULTIPLIER_CIRCUIT + 1),
+            Error::<Test>::UnknownVerifyingKey
+        );
+    });
+}
+
+#[test]
+fn batches_report_the_proof_at_fault() {
+    new_test_ext().execute_with(|| {
+        register_multiplier();
+        let statements = (0..3).map(multiplier_proof).collect::<Vec<_>>();
+        let verify = |statements: &[(Proof, Vec<u8>)]| {
+            let batch = statements
+                .iter()
+                .map(|(proof, inputs)| (proof, inputs.clone()))
+                .collect::<Vec<_>>();
+            ZkSnark::verify_batch(&batch)
+        };
+        let failure = |index, error: Error<Test>| -> Result<(), BatchFailure> {
+            Err(BatchFailure { index, error: error.into() })
+        };
+        assert_eq!(verify(&statements), Ok(()));
+
+        // Swapping the two inputs makes a well-formed statement that is false.
+        let mut false_statement = statements.clone();
+        false_statement[1].1.rotate_left(32);
+        assert_eq!(verify(&false_statement), failure(1, Error::VerificationFailed));
+
+        let mut malformed = statements.clone();
+        malformed[2].0.c.pop();
+        assert_eq!(verify(&malformed), failure(2, Error::MalformedG1));
+
+        let mut mixed = statements;
+        mixed[2].0.circuit_id = TRANSFER_CIRCUIT;
+        assert_eq!(verify(&mixed), failure(2, Error::MixedBatchCircuits));
+    });
+}
+
+#[test]
+fn an_empty_tree_has_the_zero_hash_root() {
+    new_test_ext().execute_with(|| {
+        let depth = MerkleTreeDepth::get();
+        assert_eq!(ZkSnark::current_root(), Ok(Poseidon::zero_hashes(depth)[depth as usize]));
+
+        ZkSnark::insert_leaf(H256::from_low_u64_be(1)).unwrap();
+        assert_eq!(ZkSnark::current_root(), Ok(RootHistory::<Test>::get(0).unwrap()));
+    });
+}
+
+#[test]
+fn the_oldest_root_is_evicted_once_the_history_is_full() {
+    new_test_ext().execute_with(|| {
+        let history = RootHistorySize::get();
+        for leaf in 1..=history {
+            ZkSnark::insert_leaf(H256::from_low_u64_be(leaf.into())).unwrap();
+        }
+        let oldest = RootHistory::<Test>::get(0).unwrap();
+        assert!(KnownRoots::<Test>::contains_key(oldest));
+
+        ZkSnark::insert_leaf(H256::from_low_u64_be(u64::from(history) + 1)).unwrap();
+        assert!(!KnownRoots::<Test>::contains_key(oldest));
+        assert_eq!(RootHistory::<Test>::get(0), Some(ZkSnark::current_root().unwrap()));
+        assert!(KnownRoots::<Test>::contains_key(RootHistory::<Test>::get(1).unwrap()));
+    });
+}
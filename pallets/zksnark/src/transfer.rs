@@ -0,0 +1,69 @@
+//! The statement a transfer proof is checked against.
+//!
+//! `EnhancedTransaction` exposes its public signals in this order:
+//!
+//! ```text
+//! nullifierHash, newCommitment, root, maxAmount, recipient
+//! ```
+//!
+//! circom lists a circuit's outputs before its public inputs, and the public
+//! inputs in the order the template declares them.
+//!
+//! Unlike the join-split and unshield circuits, this one has no relayer or
+//! fee inputs: a transfer re-commits the note it spends, so there is no
+//! value for a fee to come out of.
+
+use crate::Proof;
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::H256;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// One spend, as submitted to `private_transfer` or
+/// `batch_private_transfer`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct Transfer<AccountId> {
+    pub proof: Proof,
+    pub nullifier_hash: H256,
+    pub commitment: H256,
+    pub root: H256,
+    /// Account the proof was made out to.
+    pub recipient: AccountId,
+}
+
+impl<AccountId: Encode> Transfer<AccountId> {
+    /// Big-endian public inputs for the transfer circuit, with the runtime's
+    /// `max_amount`.
+    pub fn public_inputs(&self, max_amount: u64) -> Vec<u8> {
+        let mut inputs = Vec::with_capacity(5 * 32);
+        inputs.extend_from_slice(self.nullifier_hash.as_bytes());
+        inputs.extend_from_slice(self.commitment.as_bytes());
+        inputs.extend_from_slice(self.root.as_bytes());
+        inputs.extend_from_slice(&u64_input(max_amount));
+        inputs.extend_from_slice(&account_input(&self.recipient));
+        inputs
+    }
+}
+
+/// The field element an account is bound into a proof as: the BLAKE2-256
+/// hash of its SCALE encoding, big-endian, with the top three bits cleared
+/// so that it is below the BN254 and BLS12-381 scalar moduli.
+pub fn account_input<AccountId: Encode>(who: &AccountId) -> [u8; 32] {
+    let mut hash = sp_io::hashing::blake2_256(&who.encode());
+    hash[0] &= 0x1f;
+    hash
+}
+
+/// The field element notes commit to for `asset`: the same mapping as
+/// [`account_input`], applied to the asset ID.
+pub fn asset_input<AssetId: Encode>(asset: &AssetId) -> [u8; 32] {
+    account_input(asset)
+}
+
+/// `value` as a big-endian field element.
+pub(crate) fn u64_input(value: u64) -> [u8; 32] {
+    let mut input = [0u8; 32];
+    input[24..].copy_from_slice(&value.to_be_bytes());
+    input
+}
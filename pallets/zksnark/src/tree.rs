@@ -0,0 +1,84 @@
+//! Hashing and runtime storage for the pallet's commitment tree.
+//!
+//! On chain the tree keeps only its frontier: for every level, the last left
+//! child seen there. That is all [`MerkleTree::insert`] reads, so the pallet
+//! runs the same [`MerkleTree`] as off-chain tooling over a
+//! [`FrontierStore`]. Wallets rebuild full paths from `PrivateTransfer` and
+//! `JoinSplit` events.
+//!
+//! [`MerkleTree`]: crate::utils::merkle::MerkleTree
+//! [`MerkleTree::insert`]: crate::utils::merkle::MerkleTree::insert
+
+use crate::{
+    utils::merkle::{self, NodeStore},
+    Config, Frontier, NextLeafIndex,
+};
+use core::marker::PhantomData;
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+use sp_std::vec::Vec;
+
+/// Two-to-one hash used for the interior nodes of the commitment tree.
+///
+/// It must match the hash the transfer circuit uses for its membership
+/// proof, or no proof will ever verify against an on-chain root. For our
+/// circom circuits that is [`crate::poseidon::Poseidon`]; the
+/// [`BlakeTwo256`] implementation is only useful for tests and tooling.
+pub trait TreeHasher {
+    /// Hash the children of an interior node.
+    fn hash(left: &H256, right: &H256) -> H256;
+
+    /// Roots of empty subtrees of height `0..=depth`, starting with the zero
+    /// leaf. Hashers used on chain should override this with a table, since
+    /// the tree is opened on every insert.
+    fn zero_hashes(depth: u32) -> Vec<H256> {
+        merkle::zero_hashes::<Self>(depth)
+    }
+}
+
+impl TreeHasher for BlakeTwo256 {
+    fn hash(left: &H256, right: &H256) -> H256 {
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(left.as_bytes());
+        preimage[32..].copy_from_slice(right.as_bytes());
+        H256(sp_io::hashing::blake2_256(&preimage))
+    }
+}
+
+/// A [`NodeStore`] over the pallet's `Frontier` and `NextLeafIndex` storage.
+///
+/// Only left children are kept, one per level, plus the root at the top
+/// level. Right children are dropped as soon as they have been hashed.
+pub struct FrontierStore<T>(PhantomData<T>);
+
+impl<T> Default for FrontierStore<T> {
+    fn default() -> Self {
+        FrontierStore(PhantomData)
+    }
+}
+
+impl<T: Config> NodeStore for FrontierStore<T> {
+    fn leaf_count(&self) -> u64 {
+        NextLeafIndex::<T>::get().into()
+    }
+
+    fn set_leaf_count(&mut self, count: u64) {
+        // `integrity_test` bounds the depth so the count fits.
+        NextLeafIndex::<T>::put(count as u32);
+    }
+
+    fn node(&self, level: u32, index: u64) -> Option<H256> {
+        // Levels the tree has not reached yet are empty subtrees.
+        if index & 1 == 0 {
+            Frontier::<T>::get(level)
+        } else {
+            None
+        }
+    }
+
+    fn set_node(&mut self, level: u32, index: u64, node: H256) {
+        if index & 1 == 0 {
+            Frontier::<T>::insert(level, node);
+        }
+    }
+}
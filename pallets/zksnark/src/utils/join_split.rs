@@ -0,0 +1,438 @@
+//! Witnesses for the `JoinSplit` circuit.
+//!
+//! [`JoinSplitWitness::new`] checks everything the circuit will enforce, so
+//! a witness that builds also proves, and renders it as the named signals
+//! circom's witness calculator reads.
+
+use crate::{
+    join_split::{INPUTS, OUTPUTS},
+    poseidon::{self, Poseidon},
+    utils::{
+        compute_commitment, compute_nullifier_hash,
+        merkle::{MerkleError, MerkleTree, NodeStore},
+    },
+};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use serde_json::{json, Value};
+use sp_runtime::RuntimeDebug;
+use sp_std::{
+    collections::btree_map::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// `amount` of `asset`, committed to under `secret` and `nullifier`.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct Note {
+    /// The asset ID, as [`crate::transfer::asset_input`] maps it.
+    pub asset: Fr,
+    pub amount: u64,
+    pub secret: Fr,
+    pub nullifier: Fr,
+}
+
+impl Note {
+    /// The leaf the note is stored as.
+    pub fn commitment(&self) -> Fr {
+        compute_commitment(self.asset, self.amount, self.secret, self.nullifier)
+    }
+
+    /// The hash revealed when the note is spent.
+    pub fn nullifier_hash(&self) -> Fr {
+        compute_nullifier_hash(self.nullifier, self.secret)
+    }
+}
+
+/// A note to spend and the index of its commitment in the tree.
+///
+/// Spending fewer than [`INPUTS`] notes is done by padding with zero-value
+/// notes, which need not be in the tree and have no `leaf_index`. Each still
+/// needs its own `nullifier`, since its nullifier hash is recorded on chain.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct InputNote {
+    pub note: Note,
+    pub leaf_index: Option<u32>,
+}
+
+/// Reasons a witness cannot be built.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum WitnessError {
+    /// For some asset, the inputs are not worth exactly the outputs plus
+    /// the fee paid in it.
+    Unbalanced,
+    /// An input with a non-zero amount has no leaf index.
+    MissingLeafIndex,
+    /// The leaf at an input's index is not the input's commitment.
+    NoteNotInTree,
+    /// Two inputs have the same nullifier hash.
+    DuplicateNullifier,
+    /// An amount, fee or limit is outside the range the circuit checks.
+    OutOfRange,
+    /// The tree could not produce a path.
+    Merkle(MerkleError),
+}
+
+/// An input's authentication path, as `MerkleTreeChecker` takes it.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct MerklePath {
+    /// The sibling at every level from the leaf upwards.
+    pub elements: Vec<Fr>,
+    /// `pathIndices`: whether the path node is the right child at every
+    /// level.
+    pub indices: Vec<bool>,
+}
+
+impl MerklePath {
+    /// The path of `note`, whose commitment must be the leaf at `index`.
+    pub fn of_note<S: NodeStore>(
+        tree: &MerkleTree<Poseidon, S>,
+        note: &Note,
+        index: u32,
+    ) -> Result<Self, WitnessError> {
+        let leaf = tree.store().node(0, index.into());
+        if leaf != Some(poseidon::to_h256(note.commitment())) {
+            return Err(WitnessError::NoteNotInTree);
+        }
+
+        let (siblings, is_left) = tree.get_proof(index).map_err(WitnessError::Merkle)?;
+        Ok(MerklePath {
+            elements: siblings
+                .iter()
+                .map(|sibling| Fr::from_be_bytes_mod_order(sibling.as_bytes()))
+                .collect(),
+            indices: is_left.iter().map(|is_left| !is_left).collect(),
+        })
+    }
+}
+
+/// Every signal of `JoinSplit(levels, 2, 2)` that the prover supplies.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct JoinSplitWitness {
+    pub root: Fr,
+    pub inputs: [Note; INPUTS],
+    pub paths: [MerklePath; INPUTS],
+    pub outputs: [Note; OUTPUTS],
+    /// The relayer, as [`crate::transfer::account_input`] maps it.
+    pub relayer: Fr,
+    /// The asset the fee is paid in, as
+    /// [`crate::transfer::asset_input`] maps it.
+    pub fee_asset: Fr,
+    pub fee: u64,
+}
+
+impl JoinSplitWitness {
+    /// Spend `inputs` from `tree` into `outputs`, paying `fee` of
+    /// `fee_asset` to `relayer`.
+    ///
+    /// The store must keep every node so that paths can be read from it.
+    pub fn new<S: NodeStore>(
+        tree: &MerkleTree<Poseidon, S>,
+        inputs: [InputNote; INPUTS],
+        outputs: [Note; OUTPUTS],
+        relayer: Fr,
+        fee_asset: Fr,
+        fee: u64,
+    ) -> Result<Self, WitnessError> {
+        let mut balances = BTreeMap::<Fr, i128>::new();
+        for input in &inputs {
+            *balances.entry(input.note.asset).or_default() += i128::from(input.note.amount);
+        }
+        for output in &outputs {
+            *balances.entry(output.asset).or_default() -= i128::from(output.amount);
+        }
+        *balances.entry(fee_asset).or_default() -= i128::from(fee);
+        if balances.values().any(|balance| *balance != 0) {
+            return Err(WitnessError::Unbalanced);
+        }
+
+        for (i, input) in inputs.iter().enumerate() {
+            let nullifier_hash = input.note.nullifier_hash();
+            if inputs[..i]
+                .iter()
+                .any(|earlier| earlier.note.nullifier_hash() == nullifier_hash)
+            {
+                return Err(WitnessError::DuplicateNullifier);
+            }
+        }
+
+        let mut paths = Vec::with_capacity(INPUTS);
+        for input in &inputs {
+            paths.push(Self::path(tree, input)?);
+        }
+        let paths = paths.try_into().expect("one path per input; qed");
+
+        Ok(JoinSplitWitness {
+            root: Fr::from_be_bytes_mod_order(tree.root().as_bytes()),
+            inputs: inputs.map(|input| input.note),
+            paths,
+            outputs,
+            relayer,
+            fee_asset,
+            fee,
+        })
+    }
+
+    fn path<S: NodeStore>(
+        tree: &MerkleTree<Poseidon, S>,
+        input: &InputNote,
+    ) -> Result<MerklePath, WitnessError> {
+        let Some(index) = input.leaf_index else {
+            if input.note.amount != 0 {
+                return Err(WitnessError::MissingLeafIndex);
+            }
+            // The circuit skips the root check for zero-value inputs, so
+            // any path will do.
+            let depth = tree.depth() as usize;
+            return Ok(MerklePath {
+                elements: sp_std::vec![Fr::zero(); depth],
+                indices: sp_std::vec![false; depth],
+            });
+        };
+
+        MerklePath::of_note(tree, &input.note, index)
+    }
+
+    /// The circuit's public signals, in the order the verifier takes them.
+    pub fn public_signals(&self) -> Vec<Fr> {
+        let mut signals = Vec::with_capacity(INPUTS + OUTPUTS + 4);
+        signals.extend(self.inputs.iter().map(Note::nullifier_hash));
+        signals.extend(self.outputs.iter().map(Note::commitment));
+        signals.push(self.root);
+        signals.push(self.relayer);
+        signals.push(self.fee_asset);
+        signals.push(Fr::from(self.fee));
+        signals
+    }
+
+    /// The witness as a circom input file: every input signal by name, with
+    /// field elements as decimal strings.
+    pub fn circom_input(&self) -> Value {
+        let column = |notes: &[Note], signal: fn(&Note) -> String| {
+            notes.iter().map(signal).collect::<Vec<_>>()
+        };
+        let paths = |signal: fn(&MerklePath) -> Vec<String>| {
+            self.paths.iter().map(signal).collect::<Vec<_>>()
+        };
+
+        json!({
+            "inAsset": column(&self.inputs, |n| decimal(n.asset)),
+            "inAmount": column(&self.inputs, |n| n.amount.to_string()),
+            "inSecret": column(&self.inputs, |n| decimal(n.secret)),
+            "inNullifier": column(&self.inputs, |n| decimal(n.nullifier)),
+            "inPathElements": paths(|p| p.elements.iter().copied().map(decimal).collect()),
+            "inPathIndices": paths(|p| p.indices.iter().map(|i| u8::from(*i).to_string()).collect()),
+            "outAsset": column(&self.outputs, |n| decimal(n.asset)),
+            "outAmount": column(&self.outputs, |n| n.amount.to_string()),
+            "outSecret": column(&self.outputs, |n| decimal(n.secret)),
+            "outNullifier": column(&self.outputs, |n| decimal(n.nullifier)),
+            "root": decimal(self.root),
+            "relayer": decimal(self.relayer),
+            "feeAsset": decimal(self.fee_asset),
+            "fee": self.fee.to_string(),
+        })
+    }
+}
+
+fn decimal(element: Fr) -> String {
+    element.into_bigint().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tree::TreeHasher, utils::merkle::MemoryStore};
+
+    const DEPTH: u32 = 20;
+    /// Asset IDs, already mapped to field elements.
+    const NATIVE: Fr = ark_ff::MontFp!("1");
+    const TOKEN: Fr = ark_ff::MontFp!("2");
+
+    fn note(amount: u64, seed: u64) -> Note {
+        token_note(NATIVE, amount, seed)
+    }
+
+    fn token_note(asset: Fr, amount: u64, seed: u64) -> Note {
+        Note { asset, amount, secret: Fr::from(seed), nullifier: Fr::from(seed + 1_000) }
+    }
+
+    /// A tree holding notes worth 100 and 50 at indices 0 and 1.
+    fn tree() -> (MerkleTree<Poseidon, MemoryStore>, [Note; 2]) {
+        let notes = [note(100, 1), note(50, 2)];
+        let mut tree = MerkleTree::new(DEPTH, MemoryStore::default()).unwrap();
+        for note in &notes {
+            tree.insert(poseidon::to_h256(note.commitment())).unwrap();
+        }
+        (tree, notes)
+    }
+
+    fn spend(note: Note, leaf_index: u32) -> InputNote {
+        InputNote { note, leaf_index: Some(leaf_index) }
+    }
+
+    fn padding(seed: u64) -> InputNote {
+        InputNote { note: note(0, seed), leaf_index: None }
+    }
+
+    /// What `MerkleTreeChecker` computes for `leaf` and `path`.
+    fn checker_root(leaf: Fr, path: &MerklePath) -> Fr {
+        let root = path.elements.iter().zip(&path.indices).fold(
+            poseidon::to_h256(leaf),
+            |node, (sibling, is_right)| {
+                let sibling = poseidon::to_h256(*sibling);
+                if *is_right {
+                    Poseidon::hash(&sibling, &node)
+                } else {
+                    Poseidon::hash(&node, &sibling)
+                }
+            },
+        );
+        Fr::from_be_bytes_mod_order(root.as_bytes())
+    }
+
+    #[test]
+    fn balanced_witness_matches_the_tree() {
+        let (tree, [first, second]) = tree();
+        let outputs = [note(120, 3), note(20, 4)];
+        let witness = JoinSplitWitness::new(
+            &tree,
+            [spend(first, 0), spend(second, 1)],
+            outputs,
+            Fr::from(9u64),
+            NATIVE,
+            10,
+        )
+        .unwrap();
+
+        for (input, path) in witness.inputs.iter().zip(&witness.paths) {
+            assert_eq!(checker_root(input.commitment(), path), witness.root);
+        }
+        assert_eq!(
+            witness.public_signals(),
+            [
+                first.nullifier_hash(),
+                second.nullifier_hash(),
+                outputs[0].commitment(),
+                outputs[1].commitment(),
+                witness.root,
+                Fr::from(9u64),
+                NATIVE,
+                Fr::from(10u64),
+            ]
+        );
+    }
+
+    #[test]
+    fn padding_input_needs_no_leaf() {
+        let (tree, [first, _]) = tree();
+        let witness = JoinSplitWitness::new(
+            &tree,
+            [spend(first, 0), padding(5)],
+            [note(100, 3), note(0, 4)],
+            Fr::zero(),
+            NATIVE,
+            0,
+        )
+        .unwrap();
+        assert_eq!(witness.paths[1].elements, sp_std::vec![Fr::zero(); DEPTH as usize]);
+    }
+
+    #[test]
+    fn unbalanced_witness_is_rejected() {
+        let (tree, [first, second]) = tree();
+        let inputs = [spend(first, 0), spend(second, 1)];
+        for (outputs, fee) in [
+            ([note(150, 3), note(1, 4)], 0),
+            ([note(100, 3), note(40, 4)], 0),
+            ([note(100, 3), note(50, 4)], 1),
+        ] {
+            assert_eq!(
+                JoinSplitWitness::new(&tree, inputs, outputs, Fr::zero(), NATIVE, fee),
+                Err(WitnessError::Unbalanced)
+            );
+        }
+    }
+
+    #[test]
+    fn value_is_conserved_per_asset() {
+        let notes = [note(100, 1), token_note(TOKEN, 50, 2)];
+        let mut tree = MerkleTree::new(DEPTH, MemoryStore::default()).unwrap();
+        for note in &notes {
+            tree.insert(poseidon::to_h256(note.commitment())).unwrap();
+        }
+        let inputs = [spend(notes[0], 0), spend(notes[1], 1)];
+        let build = |outputs, fee_asset, fee| {
+            JoinSplitWitness::new(&tree, inputs, outputs, Fr::zero(), fee_asset, fee)
+        };
+
+        assert!(build([note(100, 3), token_note(TOKEN, 40, 4)], TOKEN, 10).is_ok());
+        assert!(build([note(90, 3), token_note(TOKEN, 50, 4)], NATIVE, 10).is_ok());
+        // The totals balance, but value moves from one asset to the other.
+        assert_eq!(
+            build([note(140, 3), token_note(TOKEN, 10, 4)], NATIVE, 0),
+            Err(WitnessError::Unbalanced)
+        );
+        assert_eq!(
+            build([note(100, 3), token_note(TOKEN, 40, 4)], NATIVE, 10),
+            Err(WitnessError::Unbalanced)
+        );
+    }
+
+    #[test]
+    fn notes_must_be_in_the_tree() {
+        let (tree, [first, second]) = tree();
+        let build = |inputs| {
+            JoinSplitWitness::new(&tree, inputs, [note(150, 3), note(0, 4)], Fr::zero(), NATIVE, 0)
+        };
+
+        let forged = Note { secret: Fr::from(99u64), ..first };
+        assert_eq!(build([spend(forged, 0), spend(second, 1)]), Err(WitnessError::NoteNotInTree));
+        assert_eq!(build([spend(first, 1), spend(second, 0)]), Err(WitnessError::NoteNotInTree));
+
+        let unindexed = InputNote { leaf_index: None, ..spend(first, 0) };
+        assert_eq!(build([unindexed, spend(second, 1)]), Err(WitnessError::MissingLeafIndex));
+    }
+
+    #[test]
+    fn a_note_cannot_be_spent_twice() {
+        let (tree, [first, _]) = tree();
+        let inputs = [spend(first, 0), spend(first, 0)];
+        assert_eq!(
+            JoinSplitWitness::new(&tree, inputs, [note(200, 3), note(0, 4)], Fr::zero(), NATIVE, 0),
+            Err(WitnessError::DuplicateNullifier)
+        );
+    }
+
+    #[test]
+    fn circom_input_names_every_signal() {
+        let (tree, [first, _]) = tree();
+        let witness = JoinSplitWitness::new(
+            &tree,
+            [spend(first, 0), padding(5)],
+            [note(90, 3), note(0, 4)],
+            Fr::zero(),
+            NATIVE,
+            10,
+        )
+        .unwrap();
+        let input = witness.circom_input();
+
+        for side in ["in", "out"] {
+            for signal in ["Asset", "Amount", "Secret", "Nullifier"] {
+                let name = format!("{side}{signal}");
+                assert_eq!(input[&name].as_array().unwrap().len(), 2, "{name}");
+            }
+        }
+        for name in ["inPathElements", "inPathIndices"] {
+            let paths = input[name].as_array().unwrap();
+            assert_eq!(paths.len(), 2, "{name}");
+            assert!(paths
+                .iter()
+                .all(|path| path.as_array().unwrap().len() == DEPTH as usize));
+        }
+        assert_eq!(input["inAmount"][0], "100");
+        assert_eq!(input["root"], decimal(witness.root));
+        assert_eq!(input["feeAsset"], "1");
+        assert_eq!(input["fee"], "10");
+    }
+}
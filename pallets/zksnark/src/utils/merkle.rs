@@ -1,149 +1,307 @@
-//zksnark/src/merkle.rs
-use sp_std::prelude::*;
-use sp_runtime::traits::Hash;
-use sp_io::hashing::poseidon;
-
-// Constants for Merkle tree and proof handling
-const MERKLE_TREE_LEVELS: usize = 20;
-
-#[derive(Clone, Debug)]
-pub struct MerkleTree<T: Hash> {
-    pub depth: u32,
-    pub leaves: Vec<T::Output>,
-    pub nodes: Vec<Vec<T::Output>>, // Nodes at each level of the tree
+//! Sparse append-only Merkle tree over [`H256`] nodes.
+//!
+//! Only nodes that have been written are kept; an empty subtree at level `l`
+//! is represented by the precomputed zero hash for that level. Appending a
+//! leaf hashes one path from the leaf to the root, reading just the left
+//! siblings on the way up, so inserts cost `O(depth)` regardless of how many
+//! leaves the tree holds. Where the nodes live is decided by a [`NodeStore`].
+
+use crate::tree::TreeHasher;
+use core::marker::PhantomData;
+use sp_core::H256;
+use sp_runtime::RuntimeDebug;
+use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
+
+/// Deepest tree supported. Leaf indices must fit in a `u32`.
+pub const MAX_DEPTH: u32 = 32;
+
+/// Reasons a tree operation can fail.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum MerkleError {
+    /// The depth is zero or greater than [`MAX_DEPTH`].
+    InvalidDepth,
+    /// Every leaf of the tree is already used.
+    TreeFull,
+    /// No leaf has been inserted at the requested index.
+    IndexOutOfBounds,
 }
 
-impl<T: Hash> MerkleTree<T> {
-    /// Create a new Merkle Tree with a given depth
-    pub fn new(depth: u32) -> Self {
-        let num_leaves = 2usize.pow(depth);
-        MerkleTree {
-            depth,
-            leaves: Vec::with_capacity(num_leaves),
-            nodes: vec![Vec::new(); depth as usize + 1],
-        }
+/// Backing storage for the nodes of a [`MerkleTree`]. Leaves are level 0.
+pub trait NodeStore {
+    /// Number of leaves appended so far.
+    fn leaf_count(&self) -> u64;
+
+    /// Record the number of leaves appended so far.
+    fn set_leaf_count(&mut self, count: u64);
+
+    /// The node at `index` within `level`, or `None` if it is not stored.
+    fn node(&self, level: u32, index: u64) -> Option<H256>;
+
+    /// Store the node at `index` within `level`.
+    fn set_node(&mut self, level: u32, index: u64, node: H256);
+}
+
+/// A [`NodeStore`] that keeps every written node in memory.
+#[derive(Clone, Default, RuntimeDebug)]
+pub struct MemoryStore {
+    leaf_count: u64,
+    nodes: BTreeMap<(u32, u64), H256>,
+}
+
+impl NodeStore for MemoryStore {
+    fn leaf_count(&self) -> u64 {
+        self.leaf_count
     }
 
-    /// Insert a single leaf into the Merkle Tree
-    pub fn insert(&mut self, leaf: T::Output) -> Result<u32, &'static str> {
-        if self.leaves.len() >= 2usize.pow(self.depth) {
-            return Err("Tree is full");
-        }
+    fn set_leaf_count(&mut self, count: u64) {
+        self.leaf_count = count;
+    }
 
-        let index = self.leaves.len() as u32;
-        self.leaves.push(leaf);
-        self.update_tree(index);
-        Ok(index)
+    fn node(&self, level: u32, index: u64) -> Option<H256> {
+        self.nodes.get(&(level, index)).copied()
     }
 
-    /// Batch insert multiple leaves
-    pub fn insert_batch(&mut self, leaves: &[T::Output]) -> Result<Vec<u32>, &'static str> {
-        if self.leaves.len() + leaves.len() > 2usize.pow(self.depth) {
-            return Err("Batch would exceed tree capacity");
-        }
+    fn set_node(&mut self, level: u32, index: u64, node: H256) {
+        self.nodes.insert((level, index), node);
+    }
+}
+
+/// An append-only Merkle tree whose empty leaves are [`H256::zero`].
+pub struct MerkleTree<H, S> {
+    depth: u32,
+    zeros: Vec<H256>,
+    store: S,
+    _hasher: PhantomData<H>,
+}
 
-        let mut indices = Vec::with_capacity(leaves.len());
-        for leaf in leaves {
-            let idx = self.insert(*leaf)?;
-            indices.push(idx);
+impl<H: TreeHasher, S: NodeStore> MerkleTree<H, S> {
+    /// Open a tree of the given depth over `store`, which may already hold
+    /// leaves from an earlier session.
+    pub fn new(depth: u32, store: S) -> Result<Self, MerkleError> {
+        if depth == 0 || depth > MAX_DEPTH {
+            return Err(MerkleError::InvalidDepth);
         }
-        Ok(indices)
+        Ok(MerkleTree { depth, zeros: H::zero_hashes(depth), store, _hasher: PhantomData })
+    }
+
+    /// Depth of the tree; it holds `2^depth` leaves.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Number of leaves inserted so far.
+    pub fn len(&self) -> u64 {
+        self.store.leaf_count()
     }
 
-    /// Update the tree after inserting a leaf
-    fn update_tree(&mut self, leaf_index: u32) {
-        let mut current_hash = self.leaves[leaf_index as usize];
-        let mut current_index = leaf_index;
+    /// Whether no leaf has been inserted yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Root of an empty subtree of height `level`.
+    pub fn zero_hash(&self, level: u32) -> H256 {
+        self.zeros[level as usize]
+    }
 
-        for level in 0..self.depth as usize {
-            let sibling_index = current_index ^ 1; // XOR for sibling index calculation
-            let sibling_hash = self.nodes[level]
-                .get(sibling_index as usize)
-                .copied()
-                .unwrap_or_default();
+    /// The backing store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
 
-            current_hash = if current_index & 1 == 0 {
-                self.hash_pair(current_hash, sibling_hash)
+    /// Give back the backing store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Append `leaf`, returning its index.
+    pub fn insert(&mut self, leaf: H256) -> Result<u32, MerkleError> {
+        let index = self.store.leaf_count();
+        if index >= 1u64 << self.depth {
+            return Err(MerkleError::TreeFull);
+        }
+
+        let mut node = leaf;
+        let mut position = index;
+        for level in 0..self.depth {
+            self.store.set_node(level, position, node);
+            node = if position & 1 == 0 {
+                H::hash(&node, &self.zeros[level as usize])
             } else {
-                self.hash_pair(sibling_hash, current_hash)
+                let left = self.node_or_zero(level, position - 1);
+                H::hash(&left, &node)
             };
+            position >>= 1;
+        }
+        self.store.set_node(self.depth, 0, node);
+        self.store.set_leaf_count(index + 1);
+
+        Ok(index as u32)
+    }
 
-            current_index >>= 1;
-            if self.nodes[level + 1].len() <= current_index as usize {
-                self.nodes[level + 1].resize(current_index as usize + 1, T::Output::default());
-            }
-            self.nodes[level + 1][current_index as usize] = current_hash;
+    /// Append several leaves, returning their indices. Nothing is inserted
+    /// if they do not all fit.
+    pub fn insert_batch(&mut self, leaves: &[H256]) -> Result<Vec<u32>, MerkleError> {
+        if self.len() + leaves.len() as u64 > 1u64 << self.depth {
+            return Err(MerkleError::TreeFull);
         }
+        leaves.iter().map(|leaf| self.insert(*leaf)).collect()
     }
 
-    /// Compute the hash of two nodes
-    fn hash_pair(&self, left: T::Output, right: T::Output) -> T::Output {
-        let mut input = Vec::with_capacity(64);
-        input.extend_from_slice(&left.as_ref());
-        input.extend_from_slice(&right.as_ref());
-        poseidon::hash(&input)
+    /// The current root.
+    pub fn root(&self) -> H256 {
+        self.node_or_zero(self.depth, 0)
     }
 
-    /// Get the Merkle root of the tree
-    pub fn get_root(&self) -> T::Output {
-        if self.nodes[self.depth as usize].is_empty() {
-            T::Output::default()
-        } else {
-            self.nodes[self.depth as usize][0]
+    /// Whether `root` is the current root.
+    pub fn verify_root(&self, root: H256) -> bool {
+        self.root() == root
+    }
+
+    /// Authentication path for the leaf at `index`: the sibling at every
+    /// level from the leaf upwards, and whether the path node is the left
+    /// child at that level. `MerkleTreeChecker` takes the negation of the
+    /// latter as its `pathIndices`.
+    ///
+    /// The store must keep every node; a store that only keeps the frontier
+    /// cannot produce paths.
+    pub fn get_proof(&self, index: u32) -> Result<(Vec<H256>, Vec<bool>), MerkleError> {
+        if u64::from(index) >= self.len() {
+            return Err(MerkleError::IndexOutOfBounds);
+        }
+
+        let mut siblings = Vec::with_capacity(self.depth as usize);
+        let mut is_left = Vec::with_capacity(self.depth as usize);
+        let mut position = u64::from(index);
+        for level in 0..self.depth {
+            siblings.push(self.node_or_zero(level, position ^ 1));
+            is_left.push(position & 1 == 0);
+            position >>= 1;
         }
+        Ok((siblings, is_left))
+    }
+
+    fn node_or_zero(&self, level: u32, index: u64) -> H256 {
+        self.store.node(level, index).unwrap_or(self.zeros[level as usize])
+    }
+}
+
+/// Roots of empty subtrees of height `0..=depth`, hashed afresh.
+pub fn zero_hashes<H: TreeHasher + ?Sized>(depth: u32) -> Vec<H256> {
+    let mut zeros = Vec::with_capacity(depth as usize + 1);
+    let mut zero = H256::zero();
+    zeros.push(zero);
+    for _ in 0..depth {
+        zero = H::hash(&zero, &zero);
+        zeros.push(zero);
+    }
+    zeros
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        poseidon::{self, Poseidon},
+        utils::compute_commitment,
+    };
+    use ark_bn254::Fr;
+    use core::str::FromStr;
+    use serde_json::Value;
+
+    /// Vectors computed independently of this crate. Every `root` is the
+    /// output of the compiled `MerkleTreeChecker` for its entry's path, and
+    /// every entry is a valid input of `circuits/test/merkle_tree_checker.circom`.
+    const VECTORS: &str = include_str!("../circuits/test/merkle_tree_vectors.json");
+
+    fn field(value: &Value) -> Fr {
+        let decimal = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        Fr::from_str(&decimal).expect("vector holds a decimal field element")
     }
 
-    /// Verify the root of the Merkle Tree
-    pub fn verify_root(&self, root: T::Output) -> bool {
-        self.get_root() == root
+    fn node(value: &Value) -> H256 {
+        poseidon::to_h256(field(value))
     }
 
-    /// Generate a proof for a given leaf index
-    pub fn get_proof(&self, index: u32) -> Result<(Vec<T::Output>, Vec<bool>), &'static str> {
-        if index >= self.leaves.len() as u32 {
-            return Err("Leaf index out of bounds");
+    fn vectors() -> (MerkleTree<Poseidon, MemoryStore>, Value) {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let depth = vectors["levels"].as_u64().unwrap() as u32;
+        let mut tree = MerkleTree::new(depth, MemoryStore::default()).unwrap();
+        for note in vectors["notes"].as_array().unwrap() {
+            let amount = note["amount"].as_u64().unwrap();
+            let commitment = compute_commitment(
+                field(&note["asset"]),
+                amount,
+                field(&note["secret"]),
+                field(&note["nullifier"]),
+            );
+            tree.insert(poseidon::to_h256(commitment)).unwrap();
         }
+        (tree, vectors)
+    }
 
-        let mut proof_elements = Vec::with_capacity(self.depth as usize);
-        let mut proof_indices = Vec::with_capacity(self.depth as usize);
-        let mut current_index = index;
+    /// The `pathElements` and `pathIndices` of a vector entry.
+    fn path(entry: &Value) -> (Vec<H256>, Vec<u8>) {
+        let elements = entry["pathElements"].as_array().unwrap().iter().map(node).collect();
+        let indices = entry["pathIndices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i.as_u64().unwrap() as u8)
+            .collect();
+        (elements, indices)
+    }
 
-        for level in 0..self.depth as usize {
-            let sibling_index = current_index ^ 1;
-            let is_left = current_index & 1 == 0;
-            proof_indices.push(is_left);
+    #[test]
+    fn root_matches_vectors() {
+        let (tree, vectors) = vectors();
+        assert_eq!(tree.root(), node(&vectors["root"]));
+    }
 
-            let sibling_hash = self.nodes[level]
-                .get(sibling_index as usize)
-                .copied()
-                .unwrap_or_default();
-            proof_elements.push(sibling_hash);
+    #[test]
+    fn get_proof_matches_vectors() {
+        let (tree, vectors) = vectors();
+        for (index, expected) in vectors["proofs"].as_array().unwrap().iter().enumerate() {
+            let (siblings, is_left) = tree.get_proof(index as u32).unwrap();
+            let (path_elements, path_indices) = path(expected);
 
-            current_index >>= 1;
+            assert_eq!(tree.store().node(0, index as u64), Some(node(&expected["leaf"])));
+            assert_eq!(siblings, path_elements);
+            assert_eq!(is_left.iter().map(|left| u8::from(!left)).collect::<Vec<_>>(), path_indices);
         }
+    }
 
-        Ok((proof_elements, proof_indices))
+    #[test]
+    fn every_path_hashes_to_the_tree_root_in_the_circuit() {
+        let (tree, vectors) = vectors();
+        for entry in vectors["proofs"].as_array().unwrap() {
+            assert_eq!(node(&entry["root"]), tree.root());
+        }
     }
-}
 
-/// Utility functions for proof handling
-pub mod utils {
-    use super::*;
+    #[test]
+    fn every_sibling_changes_the_circuit_root() {
+        // The old checker hashed the current node with itself, so any leaf
+        // verified against any root. Each `tampered` entry is the path of
+        // leaf 0 with one sibling replaced by 1.
+        let (tree, vectors) = vectors();
+        let (siblings, _) = tree.get_proof(0).unwrap();
+        let tampered = vectors["tampered"].as_array().unwrap();
+        assert_eq!(tampered.len(), siblings.len());
+        for (level, entry) in tampered.iter().enumerate() {
+            let mut expected = siblings.clone();
+            expected[level] = H256::from_low_u64_be(1);
+            assert_eq!(path(entry).0, expected);
+            assert_ne!(node(&entry["root"]), tree.root());
+        }
+    }
 
-    /// Compute a nullifier hash using Poseidon
-    pub fn compute_nullifier_hash(nullifier: &[u8], secret: &[u8]) -> Vec<u8> {
-        let mut input = Vec::with_capacity(64);
-        input.extend_from_slice(nullifier);
-        input.extend_from_slice(secret);
-        poseidon::hash(&input).to_vec()
-    }
-
-    /// Compute a commitment using Poseidon
-    pub fn compute_commitment(amount: u64, secret: &[u8], nullifier: &[u8]) -> Vec<u8> {
-        let mut input = Vec::with_capacity(72);
-        input.extend_from_slice(&amount.to_le_bytes());
-        input.extend_from_slice(secret);
-        input.extend_from_slice(nullifier);
-        poseidon::hash(&input).to_vec()
+    #[test]
+    fn get_proof_rejects_missing_leaf() {
+        let (tree, _) = vectors();
+        assert_eq!(tree.get_proof(tree.len() as u32), Err(MerkleError::IndexOutOfBounds));
     }
 }
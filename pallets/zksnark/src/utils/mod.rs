@@ -0,0 +1,19 @@
+//! Helpers shared by the pallet and off-chain tooling.
+
+pub mod join_split;
+pub mod merkle;
+
+use crate::poseidon;
+use ark_bn254::Fr;
+
+/// Commitment to a note, `Poseidon(asset, amount, secret, nullifier)`, as
+/// computed by `EnhancedTransaction`. `asset` is the asset ID as
+/// [`crate::transfer::asset_input`] maps it.
+pub fn compute_commitment(asset: Fr, amount: u64, secret: Fr, nullifier: Fr) -> Fr {
+    poseidon::hash4(asset, Fr::from(amount), secret, nullifier)
+}
+
+/// Hash revealed when a note is spent, `Poseidon(nullifier, secret)`.
+pub fn compute_nullifier_hash(nullifier: Fr, secret: Fr) -> Fr {
+    poseidon::hash2(nullifier, secret)
+}
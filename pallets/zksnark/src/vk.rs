@@ -0,0 +1,289 @@
+//! Parsing of `verification_key.json` files as emitted by
+//! `snarkjs zkey export verificationkey`.
+//!
+//! snarkjs writes every coordinate as a decimal string and every point in
+//! projective form. G1 points are `[x, y, z]` and G2 points are
+//! `[[x.c0, x.c1], [y.c0, y.c1], [z.c0, z.c1]]`. Exported keys are always
+//! normalised, so `z` must be one (or zero for the point at infinity).
+
+use crate::curve::{checked_point, PointError};
+use ark_ec::{
+    pairing::Pairing,
+    short_weierstrass::{Affine, SWCurveConfig},
+};
+use ark_ff::{
+    fields::models::{Fp, FpConfig, QuadExtConfig, QuadExtField},
+    Field, One, PrimeField, Zero,
+};
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use core::str::FromStr;
+use serde_json::Value;
+use sp_std::{string::ToString, vec, vec::Vec};
+
+/// The only proving system this module understands.
+pub const PROTOCOL_GROTH16: &str = "groth16";
+
+/// Reasons a snarkjs verification key can be rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationKeyError {
+    /// The bytes are not valid JSON.
+    InvalidJson,
+    /// A required field is missing or has the wrong JSON type.
+    MissingField(&'static str),
+    /// The `protocol` field is not `groth16`.
+    UnsupportedProtocol,
+    /// The `curve` field does not match the curve the key is decoded for.
+    CurveMismatch,
+    /// A coordinate is not a canonical decimal field element.
+    InvalidFieldElement,
+    /// A point is not a JSON array of three coordinates.
+    MalformedPoint,
+    /// A point is not in the normalised `z = 1` form.
+    NotNormalized,
+    /// A point is off the curve or outside the prime-order subgroup.
+    InvalidPoint(PointError),
+    /// `nPublic` disagrees with the number of `IC` points.
+    PublicInputCountMismatch,
+}
+
+/// A field whose elements can be read from and written as snarkjs JSON
+/// coordinates.
+pub trait SnarkjsField: Field {
+    fn from_json(value: &Value) -> Result<Self, VerificationKeyError>;
+
+    fn to_json(&self) -> Value;
+}
+
+impl<P: FpConfig<N>, const N: usize> SnarkjsField for Fp<P, N> {
+    fn from_json(value: &Value) -> Result<Self, VerificationKeyError> {
+        let s = value.as_str().ok_or(VerificationKeyError::InvalidFieldElement)?;
+        let repr = <Self as PrimeField>::BigInt::from_str(s)
+            .map_err(|_| VerificationKeyError::InvalidFieldElement)?;
+        // `from_bigint` refuses values >= the modulus, so every element has a
+        // single accepted encoding.
+        Self::from_bigint(repr).ok_or(VerificationKeyError::InvalidFieldElement)
+    }
+
+    fn to_json(&self) -> Value {
+        Value::String(self.into_bigint().to_string())
+    }
+}
+
+impl<P: QuadExtConfig> SnarkjsField for QuadExtField<P>
+where
+    P::BaseField: SnarkjsField,
+{
+    fn from_json(value: &Value) -> Result<Self, VerificationKeyError> {
+        match value.as_array().map(Vec::as_slice) {
+            Some([c0, c1]) => Ok(QuadExtField::new(
+                P::BaseField::from_json(c0)?,
+                P::BaseField::from_json(c1)?,
+            )),
+            _ => Err(VerificationKeyError::InvalidFieldElement),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        Value::Array(vec![self.c0.to_json(), self.c1.to_json()])
+    }
+}
+
+/// A curve point that can be read from and written as a snarkjs projective
+/// triple.
+pub trait SnarkjsPoint: Sized {
+    fn from_json(value: &Value) -> Result<Self, VerificationKeyError>;
+
+    fn to_json(&self) -> Value;
+}
+
+impl<P: SWCurveConfig> SnarkjsPoint for Affine<P>
+where
+    P::BaseField: SnarkjsField,
+{
+    fn from_json(value: &Value) -> Result<Self, VerificationKeyError> {
+        let [x, y, z] = match value.as_array().map(Vec::as_slice) {
+            Some([x, y, z]) => [x, y, z],
+            _ => return Err(VerificationKeyError::MalformedPoint),
+        };
+        let x = P::BaseField::from_json(x)?;
+        let y = P::BaseField::from_json(y)?;
+        let z = P::BaseField::from_json(z)?;
+
+        if z.is_zero() {
+            // snarkjs writes the identity as `[0, 1, 0]`.
+            if x.is_zero() && y.is_one() {
+                return Ok(Affine::identity());
+            }
+            return Err(VerificationKeyError::NotNormalized);
+        }
+        if !z.is_one() {
+            return Err(VerificationKeyError::NotNormalized);
+        }
+
+        checked_point(x, y).map_err(VerificationKeyError::InvalidPoint)
+    }
+
+    fn to_json(&self) -> Value {
+        let (zero, one) = (P::BaseField::zero(), P::BaseField::one());
+        let [x, y, z] = if self.infinity { [zero, one, zero] } else { [self.x, self.y, one] };
+        Value::Array(vec![x.to_json(), y.to_json(), z.to_json()])
+    }
+}
+
+/// Parse a snarkjs Groth16 verification key for the pairing engine `E`.
+///
+/// `curve` is the name snarkjs uses for `E` (`bn128`, `bls12381`) and must
+/// match the key's `curve` field.
+pub fn parse_snarkjs<E: Pairing>(
+    vk_bytes: &[u8],
+    curve: &str,
+) -> Result<VerifyingKey<E>, VerificationKeyError>
+where
+    E::G1Affine: SnarkjsPoint,
+    E::G2Affine: SnarkjsPoint,
+{
+    let vk: Value =
+        serde_json::from_slice(vk_bytes).map_err(|_| VerificationKeyError::InvalidJson)?;
+
+    let protocol = vk["protocol"]
+        .as_str()
+        .ok_or(VerificationKeyError::MissingField("protocol"))?;
+    if protocol != PROTOCOL_GROTH16 {
+        return Err(VerificationKeyError::UnsupportedProtocol);
+    }
+
+    let key_curve = vk["curve"]
+        .as_str()
+        .ok_or(VerificationKeyError::MissingField("curve"))?;
+    if !key_curve.eq_ignore_ascii_case(curve) {
+        return Err(VerificationKeyError::CurveMismatch);
+    }
+
+    let n_public = vk["nPublic"]
+        .as_u64()
+        .ok_or(VerificationKeyError::MissingField("nPublic"))?;
+
+    let ic = vk["IC"]
+        .as_array()
+        .ok_or(VerificationKeyError::MissingField("IC"))?;
+    if ic.len() as u64 != n_public + 1 {
+        return Err(VerificationKeyError::PublicInputCountMismatch);
+    }
+
+    let gamma_abc_g1 = ic
+        .iter()
+        .map(E::G1Affine::from_json)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(VerifyingKey {
+        alpha_g1: field::<E::G1Affine>(&vk, "vk_alpha_1")?,
+        beta_g2: field::<E::G2Affine>(&vk, "vk_beta_2")?,
+        gamma_g2: field::<E::G2Affine>(&vk, "vk_gamma_2")?,
+        delta_g2: field::<E::G2Affine>(&vk, "vk_delta_2")?,
+        gamma_abc_g1,
+    })
+}
+
+/// Write `vk` as `snarkjs zkey export verificationkey` does. `curve` is the
+/// name snarkjs uses for `E`.
+pub fn to_snarkjs<E: Pairing>(vk: &VerifyingKey<E>, curve: &str) -> Vec<u8>
+where
+    E::G1Affine: SnarkjsPoint,
+    E::G2Affine: SnarkjsPoint,
+{
+    let key = serde_json::json!({
+        "protocol": PROTOCOL_GROTH16,
+        "curve": curve,
+        "nPublic": vk.gamma_abc_g1.len().saturating_sub(1),
+        "vk_alpha_1": vk.alpha_g1.to_json(),
+        "vk_beta_2": vk.beta_g2.to_json(),
+        "vk_gamma_2": vk.gamma_g2.to_json(),
+        "vk_delta_2": vk.delta_g2.to_json(),
+        "IC": vk.gamma_abc_g1.iter().map(SnarkjsPoint::to_json).collect::<Vec<_>>(),
+    });
+    serde_json::to_vec(&key).expect("serializing a JSON value cannot fail; qed")
+}
+
+fn field<P: SnarkjsPoint>(vk: &Value, name: &'static str) -> Result<P, VerificationKeyError> {
+    let value = vk.get(name).ok_or(VerificationKeyError::MissingField(name))?;
+    P::from_json(value)
+}
+
+/// Prepare `vk` for verification and serialize the result for storage.
+///
+/// The prepared form carries the precomputed `e(alpha, beta)` pairing and the
+/// line coefficients of `-gamma` and `-delta`, so verification against it
+/// skips the most expensive part of `prepare_verifying_key`.
+pub fn serialize_prepared<E: Pairing>(vk: &VerifyingKey<E>) -> Vec<u8> {
+    let pvk = prepare_verifying_key(vk);
+    let mut bytes = Vec::with_capacity(pvk.uncompressed_size());
+    pvk.serialize_uncompressed(&mut bytes)
+        .expect("writing to a Vec cannot fail; qed");
+    bytes
+}
+
+/// Decode a key written by [`serialize_prepared`].
+///
+/// Points are not re-validated: the key was checked when it was parsed from
+/// JSON, and only the pallet writes the serialized form.
+pub fn deserialize_prepared<E: Pairing>(
+    bytes: &[u8],
+) -> Result<PreparedVerifyingKey<E>, SerializationError> {
+    PreparedVerifyingKey::deserialize_uncompressed_unchecked(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::Circuit;
+    use ark_bn254::Bn254;
+
+    const KEY: &[u8] = Circuit::Transfer.verification_key();
+
+    fn edited(edit: impl FnOnce(&mut Value)) -> Vec<u8> {
+        let mut key: Value = serde_json::from_slice(KEY).unwrap();
+        edit(&mut key);
+        serde_json::to_vec(&key).unwrap()
+    }
+
+    #[test]
+    fn parses_the_shipped_key() {
+        let key: Value = serde_json::from_slice(KEY).unwrap();
+        let vk = parse_snarkjs::<Bn254>(KEY, "bn128").unwrap();
+        assert_eq!(Some(vk.gamma_abc_g1.len() as u64 - 1), key["nPublic"].as_u64());
+        assert_eq!(parse_snarkjs::<Bn254>(&to_snarkjs(&vk, "bn128"), "bn128"), Ok(vk));
+    }
+
+    #[test]
+    fn rejects_keys_for_other_protocols_and_curves() {
+        let plonk = edited(|key| key["protocol"] = "plonk".into());
+        assert_eq!(
+            parse_snarkjs::<Bn254>(&plonk, "bn128"),
+            Err(VerificationKeyError::UnsupportedProtocol)
+        );
+        assert_eq!(
+            parse_snarkjs::<Bn254>(KEY, "bls12381"),
+            Err(VerificationKeyError::CurveMismatch)
+        );
+        let unnamed = edited(|key| key["curve"] = Value::Null);
+        assert_eq!(
+            parse_snarkjs::<Bn254>(&unnamed, "bn128"),
+            Err(VerificationKeyError::MissingField("curve"))
+        );
+    }
+
+    #[test]
+    fn rejects_an_input_count_the_points_disagree_with() {
+        for delta in [-1i64, 1] {
+            let key = edited(|key| {
+                let n_public = key["nPublic"].as_i64().unwrap();
+                key["nPublic"] = (n_public + delta).into();
+            });
+            assert_eq!(
+                parse_snarkjs::<Bn254>(&key, "bn128"),
+                Err(VerificationKeyError::PublicInputCountMismatch)
+            );
+        }
+    }
+}
@@ -21,21 +21,26 @@ use pallet_timestamp::{Pallet as TimestampPallet, Call as TimestampCall, Inheren
 use pallet_aura::{Pallet as AuraPallet, Call as AuraCall, Config as AuraConfig};
 use pallet_grandpa::{Pallet as GrandpaPallet, Call as GrandpaCall, Storage as GrandpaStorage, Config as GrandpaConfig, Event as GrandpaEvent};
 use pallet_balances::{Pallet as BalancesPallet, Call as BalancesCall, Storage as BalancesStorage, Config as BalancesConfig, Event as BalancesEvent};
+use frame_support::traits::{
+    fungible::{NativeFromLeft, NativeOrWithId, UnionOf},
+    AsEnsureOriginWithArg,
+};
 use pallet_transaction_payment::{Pallet as TransactionPaymentPallet, Storage as TransactionPaymentStorage};
 use pallet_sudo::{Pallet as SudoPallet, Call as SudoCall, Storage as SudoStorage, Config as SudoConfig, Event as SudoEvent};
 use zk_snarks_lib::{generate_zk_proof, verify_zk_proof, utils::random_witness};
 use pallet_zksnark;
 
 parameter_types! {
+    pub const ZkSnarkPalletId: frame_support::PalletId = frame_support::PalletId(*b"zk/shpol");
     pub const MerkleTreeDepth: u32 = 20;
+    pub const RootHistorySize: u32 = 30;
+    pub const MaxTransferAmount: u64 = u32::MAX as u64;
+    pub const TransferCircuitId: u32 = 0;
+    pub const ShieldCircuitId: u32 = 2;
+    pub const UnshieldCircuitId: u32 = 3;
+    pub const JoinSplitCircuitId: u32 = 1;
     pub const MaxBatchSize: u32 = 4;
-}
-
-impl pallet_zksnark::Config for Runtime {
-    type Event = Event;
-    type Currency = Balances;
-    type MerkleTreeDepth = MerkleTreeDepth;
-    type MaxBatchSize = MaxBatchSize;
+    pub const MaxVerifyingKeyLength: u32 = 128 * 1024;
 }
 use sp_std::{format, prelude::*, vec::Vec};
 
@@ -107,6 +112,7 @@ construct_runtime!(
         Aura: pallet_aura::{Pallet, Call, Storage, Config<T>},
         Grandpa: pallet_grandpa::{Pallet, Call, Storage, Config, Event<T>},
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Assets: pallet_assets,
         TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
         Sudo: pallet_sudo::{Pallet, Call, Storage, Config<T>, Event<T>},
 
@@ -203,6 +209,42 @@ impl pallet_balances::Config for Runtime {
     type WeightInfo = ();
 }
 
+// Assets Config
+parameter_types! {
+    pub const AssetDeposit: Balance = 100 * 1_000_000_000;
+    pub const AssetAccountDeposit: Balance = 1_000_000_000;
+    pub const MetadataDepositBase: Balance = 10 * 1_000_000_000;
+    pub const MetadataDepositPerByte: Balance = 1_000_000_000;
+    pub const ApprovalDeposit: Balance = 1_000_000_000;
+}
+
+impl pallet_assets::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type AssetId = u32;
+    type AssetIdParameter = u32;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type AssetDeposit = AssetDeposit;
+    type AssetAccountDeposit = AssetAccountDeposit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type ApprovalDeposit = ApprovalDeposit;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type Extra = ();
+    type CallbackHandle = ();
+    type RemoveItemsLimit = ConstU32<1000>;
+    type WeightInfo = ();
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+/// The native currency alongside every asset issued through `Assets`.
+pub type NativeAndAssets =
+    UnionOf<Balances, Assets, NativeFromLeft, NativeOrWithId<u32>, AccountId>;
+
 // Transaction Payment Config
 impl pallet_transaction_payment::Config for Runtime {}
 
@@ -212,127 +254,24 @@ impl pallet_sudo::Config for Runtime {
     type Call = Call;
 }
 
+// ZkSnark Config
 impl pallet_zksnark::Config for Runtime {
-    type RuntimeEvent = RuntimeEvent; // Ensure this is consistent with your runtime event type.
-    type VerifyingKey = VerifyingKeyStorage<Self>;
-    type WeightInfo = ();
-}
-
-
-// ------------------------
-// Verifying Key Storage
-// ------------------------
-
-pub struct VerifyingKeyStorage<T>(sp_std::marker::PhantomData<T>);
-
-impl<T: frame_system::Config> pallet_zksnark::VerifyingKeyStorage<T> for VerifyingKeyStorage<T> {
-    type Key = Vec<u8>;
-
-    fn put(key: Vec<u8>) -> DispatchResult {
-        <VerifyingKey<T>>::put(key);
-        Ok(())
-    }
-
-    fn get() -> Option<Vec<u8>> {
-        <VerifyingKey<T>>::get()
-    }
-}
-
-// ------------------------
-// Define Storage Item for Verifying Key
-// ------------------------
-
-#[frame_support::pallet]
-pub mod pallet_zksnark {
-    use super::*;
-    use frame_support::pallet_prelude::*;
-
-    #[pallet::pallet]
-    #[pallet::generate_store(pub(super) trait Store)]
-    pub struct Pallet<T>(_);
-
-    #[pallet::storage]
-    pub(super) type Commitments<T: Config> = StorageMap<_, Blake2_128Concat, H256, ()>;
-
-    #[pallet::storage]
-    pub(super) type NullifierSet<T: Config> = StorageMap<_, Blake2_128Concat, H256, ()>;
-
-    #[pallet::config]
-    pub trait Config: frame_system::Config {
-        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
-        type VerifyingKey: VerifyingKeyStorage<Self>;
-        // Add other associated types as needed
-    }
-
-    #[pallet::event]
-    #[pallet::generate_deposit(pub(super) fn deposit_event)]
-    pub enum Event<T: Config> {
-        VerifyingKeyUpdated,
-        PrivateTransfer(T::AccountId, H256),
-        // Add other events as needed
-    }
-
-    #[pallet::error]
-    pub enum Error<T> {
-        NullifierAlreadyUsed,
-        InvalidProof,
-        // Add other errors as needed
-    }
-
-    #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        #[pallet::weight(10_000)]
-        pub fn private_transfer(
-            origin: OriginFor<T>,
-            proof: Vec<u8>,
-            nullifier_hash: H256,
-            commitment: H256,
-            root: H256,
-        ) -> DispatchResult {
-            let who = ensure_signed(origin)?;
-            
-            // Verify nullifier hasn't been spent
-            ensure!(!NullifierSet::<T>::contains_key(nullifier_hash), Error::<T>::NullifierAlreadyUsed);
-            
-            // Verify proof
-            let public_inputs = [root.as_bytes(), nullifier_hash.as_bytes(), commitment.as_bytes()].concat();
-            ensure!(Self::verify_proof(&proof, &public_inputs)?, Error::<T>::InvalidProof);
-            
-            // Update state
-            NullifierSet::<T>::insert(nullifier_hash, ());
-            Commitments::<T>::insert(commitment, ());
-            
-            Self::deposit_event(Event::PrivateTransfer(who, commitment));
-            Ok(())
-        }
-
-        // Add other dispatchable functions as needed
-    }
-
-    // Implement GenesisBuild for ZkSnark Pallet
-    impl<T: Config> BuildGenesisConfig<T> for GenesisConfig<T> {
-        fn build(&self) {
-            if !self.verifying_key.is_empty() {
-                <VerifyingKey<T>>::put(self.verifying_key.clone());
-                Pallet::<T>::deposit_event(Event::VerifyingKeyUpdated);
-            }
-        }
-    }
-}
-
-// ------------------------
-// Genesis Configuration
-// ------------------------
-
-#[pallet::genesis_config]
-pub struct GenesisConfig<T: Config> {
-    pub verifying_key: Vec<u8>,
-}
-
-impl<T: Config> Default for GenesisConfig<T> {
-    fn default() -> Self {
-        GenesisConfig {
-            verifying_key: Vec::new(),
-        }
-    }
+    type RuntimeEvent = RuntimeEvent;
+    type Assets = NativeAndAssets;
+    type PalletId = ZkSnarkPalletId;
+    type MaxVerifyingKeyLength = MaxVerifyingKeyLength;
+    type MerkleTreeDepth = MerkleTreeDepth;
+    type RootHistorySize = RootHistorySize;
+    type TreeHasher = pallet_zksnark::poseidon::Poseidon;
+    type MaxTransferAmount = MaxTransferAmount;
+    type TransferCircuitId = TransferCircuitId;
+    type ShieldCircuitId = ShieldCircuitId;
+    type UnshieldCircuitId = UnshieldCircuitId;
+    type JoinSplitCircuitId = JoinSplitCircuitId;
+    type MaxBatchSize = MaxBatchSize;
+    type RegistryOrigin = frame_system::EnsureRoot<AccountId>;
+    type PoolOrigin = frame_system::EnsureRoot<AccountId>;
+    type WeightInfo = pallet_zksnark::weights::SubstrateWeight<Runtime>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
 }